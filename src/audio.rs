@@ -1,4 +1,5 @@
 use crate::loading::AudioAssets;
+use crate::session::{MatchEntity, MatchSetupSet};
 use crate::GameState;
 use bevy::audio::PlaybackMode;
 use bevy::prelude::*;
@@ -11,7 +12,9 @@ impl Plugin for InternalAudioPlugin {
         app.add_systems(OnEnter(GameState::Menu), start_go_audio)
             .add_systems(
                 OnEnter(GameState::Playing),
-                (stop_go_audio, start_game_audio).chain(),
+                (stop_go_audio, start_game_audio)
+                    .chain()
+                    .in_set(MatchSetupSet),
             );
     }
 }
@@ -19,8 +22,12 @@ impl Plugin for InternalAudioPlugin {
 #[derive(Component)]
 struct GoAudio;
 
-fn start_go_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, mut init: Local<bool>) {
-    if !*init {
+fn start_go_audio(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    go_audio: Query<(), With<GoAudio>>,
+) {
+    if go_audio.is_empty() {
         commands.spawn((
             AudioBundle {
                 source: audio_assets.go.clone(),
@@ -31,7 +38,6 @@ fn start_go_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, mut in
             },
             GoAudio,
         ));
-        *init = true;
     }
 }
 
@@ -42,11 +48,14 @@ fn stop_go_audio(mut commands: Commands, go_audio: Query<Entity, With<GoAudio>>)
 }
 
 fn start_game_audio(mut commands: Commands, audio_assets: Res<AudioAssets>) {
-    commands.spawn(AudioBundle {
-        source: audio_assets.game.clone(),
-        settings: PlaybackSettings {
-            mode: PlaybackMode::Loop,
-            ..Default::default()
+    commands.spawn((
+        AudioBundle {
+            source: audio_assets.game.clone(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                ..Default::default()
+            },
         },
-    });
+        MatchEntity,
+    ));
 }
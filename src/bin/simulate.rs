@@ -0,0 +1,42 @@
+//! Plays a headless match with a scripted player and prints a summary
+//!
+//! Usage: `cargo run --bin simulate -- [idle|soldiers|shield-then-soldiers] [max minutes] [seed] [rush|turtle|tech] [easy|normal|hard]`
+
+use std::time::Duration;
+
+use bevy_jam4_click_defense::simulation::{
+    run_simulation, Difficulty, MatchSettings, PlayerPolicy, SimulationConfig, Strategy,
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut config = SimulationConfig::default();
+
+    if let Some(policy) = args.next() {
+        config.policy = policy
+            .parse::<PlayerPolicy>()
+            .unwrap_or_else(|error| panic!("{error}"));
+    }
+    if let Some(minutes) = args.next() {
+        let minutes: u64 = minutes.parse().expect("max minutes must be a number");
+        config.max_duration = Duration::from_secs(minutes * 60);
+    }
+    if let Some(seed) = args.next() {
+        config.seed = seed.parse().expect("seed must be a number");
+    }
+    let strategy = args.next().map(|strategy| {
+        strategy
+            .parse::<Strategy>()
+            .unwrap_or_else(|error| panic!("{error}"))
+    });
+    if let Some(difficulty) = args.next() {
+        config.settings = MatchSettings::preset(
+            difficulty
+                .parse::<Difficulty>()
+                .unwrap_or_else(|error| panic!("{error}")),
+        );
+    }
+    config.settings.enemy_strategy = strategy;
+
+    println!("{}", run_simulation(config));
+}
@@ -0,0 +1,421 @@
+use std::{collections::HashMap, str::FromStr};
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::SimulationSet,
+    common::{
+        rng::{seed_rng, GameRng},
+        Faction,
+    },
+    loading::TextureAssets,
+    session::{MatchSettings, MatchSetupSet},
+    units::{
+        definition::UnitRegistry,
+        upgrade::{AttackCooldownUpgrade, ShieldUpgrade},
+        UnitKind,
+    },
+    GameState,
+};
+
+use super::{
+    spawner::{tick_wave_timer, EnemyUpgrade, Wave},
+    tech::{Research, ResearchCompleted, TechRegistry, TechStatus},
+    tower::{spawn_tower, Tower, TowerSlots},
+    upgrade::SpawnCooldownReduction,
+    EnemyCastle, SpawnQueue,
+};
+
+pub struct CommanderPlugin;
+
+/// This plugin handles the enemy commander, which earns gold and spends it on the same units, upgrades, towers and research as the player
+/// Commander logic is only active during the State `GameState::Playing`
+impl Plugin for CommanderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Commander>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                init_commander.after(seed_rng).in_set(MatchSetupSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (earn_commander_income, commander_think)
+                    .chain()
+                    .after(tick_wave_timer)
+                    .in_set(SimulationSet::Commands)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How well the commander plays, set by the [`MatchSettings`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CommanderParameters {
+    /// Gold per second, on top of the gold every wave brings
+    pub income: f32,
+    /// Seconds between two purchases
+    pub think_interval: f32,
+    /// Chance from 0 to 1 that a unit is picked to counter the army of the player instead of by strategy
+    pub counter_chance: f32,
+    /// The commander saves its gold for other things while this many units are queued
+    pub max_queued: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Strategy {
+    /// Floods the battlefield with soldiers and attack speed
+    Rush,
+    /// Builds towers and armor, attacks with archers from behind
+    Turtle,
+    /// Researches knights and upgrades before it commits to an army
+    Tech,
+}
+
+impl Strategy {
+    pub const ALL: [Self; 3] = [Self::Rush, Self::Turtle, Self::Tech];
+
+    /// Share of the gold spent on each kind of purchase
+    fn budget(&self, spending: Spending) -> f32 {
+        match (self, spending) {
+            (Strategy::Rush, Spending::Units) => 0.8,
+            (Strategy::Rush, Spending::Upgrades) => 0.15,
+            (Strategy::Rush, Spending::Research) => 0.05,
+            (Strategy::Rush, Spending::Towers) => 0.0,
+            (Strategy::Turtle, Spending::Units) => 0.45,
+            (Strategy::Turtle, Spending::Upgrades) => 0.25,
+            (Strategy::Turtle, Spending::Research) => 0.05,
+            (Strategy::Turtle, Spending::Towers) => 0.25,
+            (Strategy::Tech, Spending::Units) => 0.45,
+            (Strategy::Tech, Spending::Upgrades) => 0.15,
+            (Strategy::Tech, Spending::Research) => 0.4,
+            (Strategy::Tech, Spending::Towers) => 0.0,
+        }
+    }
+
+    /// Upgrades in order of preference, the cheapest one is bought
+    fn upgrades(&self) -> [EnemyUpgrade; 3] {
+        match self {
+            Strategy::Rush => [
+                EnemyUpgrade::AttackCooldown,
+                EnemyUpgrade::SpawnCooldownReduction,
+                EnemyUpgrade::Shield,
+            ],
+            Strategy::Turtle => [
+                EnemyUpgrade::Shield,
+                EnemyUpgrade::AttackCooldown,
+                EnemyUpgrade::SpawnCooldownReduction,
+            ],
+            Strategy::Tech => [
+                EnemyUpgrade::Shield,
+                EnemyUpgrade::SpawnCooldownReduction,
+                EnemyUpgrade::AttackCooldown,
+            ],
+        }
+    }
+
+    /// Unit the strategy builds its army around
+    fn unit(&self) -> UnitKind {
+        match self {
+            Strategy::Rush => UnitKind::Soldier,
+            Strategy::Turtle => UnitKind::Archer,
+            Strategy::Tech => UnitKind::Knight,
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rush" => Ok(Self::Rush),
+            "turtle" => Ok(Self::Turtle),
+            "tech" => Ok(Self::Tech),
+            _ => Err(format!(
+                "Unknown strategy {s}, expected one of: rush, turtle, tech"
+            )),
+        }
+    }
+}
+
+/// What the commander spends its gold on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Spending {
+    Units,
+    Upgrades,
+    Research,
+    Towers,
+}
+
+impl Spending {
+    const ALL: [Self; 4] = [Self::Units, Self::Upgrades, Self::Research, Self::Towers];
+}
+
+/// The enemy player of the current match
+#[derive(Debug, Resource)]
+pub struct Commander {
+    pub gold: usize,
+    pub strategy: Strategy,
+    pub parameters: CommanderParameters,
+    /// Gold spent so far
+    pub spent: HashMap<Spending, usize>,
+    /// Income that did not add up to a whole coin yet
+    income: f32,
+    think_timer: Timer,
+}
+
+impl Default for Commander {
+    fn default() -> Self {
+        Self::new(Strategy::Rush, MatchSettings::default().commander)
+    }
+}
+
+impl Commander {
+    pub fn new(strategy: Strategy, parameters: CommanderParameters) -> Self {
+        Self {
+            gold: 0,
+            strategy,
+            parameters,
+            spent: HashMap::new(),
+            income: 0.0,
+            think_timer: Timer::from_seconds(parameters.think_interval, TimerMode::Repeating),
+        }
+    }
+
+    /// Spendings ordered by how far they are behind their share of the budget
+    fn priorities(&self) -> Vec<Spending> {
+        let total: usize = self.spent.values().sum();
+        let deficit = |spending: Spending| {
+            self.strategy.budget(spending) * total as f32
+                - self.spent.get(&spending).copied().unwrap_or_default() as f32
+        };
+
+        let mut priorities: Vec<Spending> = Spending::ALL
+            .into_iter()
+            .filter(|spending| self.strategy.budget(*spending) > 0.0)
+            .collect();
+        priorities.sort_by(|a, b| deficit(*b).total_cmp(&deficit(*a)));
+        priorities
+    }
+
+    fn spend(&mut self, spending: Spending, cost: usize) {
+        self.gold -= cost;
+        *self.spent.entry(spending).or_default() += cost;
+    }
+}
+
+/// Something the commander can buy
+#[derive(Debug, Clone, Copy)]
+enum Purchase {
+    Unit(UnitKind),
+    Upgrade(EnemyUpgrade),
+    /// Index of the node of the tech tree
+    Research(usize),
+    Tower(usize),
+}
+
+fn init_commander(mut commands: Commands, settings: Res<MatchSettings>, mut rng: ResMut<GameRng>) {
+    let strategy = settings
+        .enemy_strategy
+        .unwrap_or_else(|| *Strategy::ALL.choose(&mut *rng).unwrap());
+    info!("Enemy strategy: {:?}", strategy);
+    commands.insert_resource(Commander::new(strategy, settings.commander));
+}
+
+fn earn_commander_income(time: Res<Time>, wave: Res<Wave>, mut commander: ResMut<Commander>) {
+    /* The income grows by a tenth with every wave */
+    let growth = 1.0 + 0.1 * wave.level.saturating_sub(1) as f32;
+    commander.income += commander.parameters.income * growth * time.delta_seconds();
+
+    let coins = commander.income.floor();
+    commander.gold += coins as usize;
+    commander.income -= coins;
+}
+
+/// Units of each kind a faction has on the battlefield
+fn army(units: &Query<(&UnitKind, &Faction)>, faction: Faction) -> HashMap<UnitKind, usize> {
+    let mut army = HashMap::new();
+    for (kind, unit_faction) in units {
+        if *unit_faction == faction {
+            *army.entry(*kind).or_default() += 1;
+        }
+    }
+    army
+}
+
+/// Melee units close the distance to archers and knights shrug off arrows, archers outrange melee units
+fn counter(player_army: &HashMap<UnitKind, usize>, player_towers: usize) -> UnitKind {
+    let count = |kind| player_army.get(&kind).copied().unwrap_or_default();
+    let melee = count(UnitKind::Soldier) + count(UnitKind::Knight);
+    let ranged = count(UnitKind::Archer) + player_towers;
+
+    if ranged > melee {
+        UnitKind::Knight
+    } else {
+        UnitKind::Archer
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn commander_think(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut commander: ResMut<Commander>,
+    enemy_castle: Res<EnemyCastle>,
+    mut castles: Query<(
+        &GlobalTransform,
+        &mut SpawnQueue,
+        &mut SpawnCooldownReduction,
+        &mut ShieldUpgrade,
+        &mut AttackCooldownUpgrade,
+        &mut TowerSlots,
+        &mut Research,
+    )>,
+    units: Query<(&UnitKind, &Faction)>,
+    towers: Query<(), With<Tower>>,
+    player_towers: Query<&Faction, With<Tower>>,
+    mut rng: ResMut<GameRng>,
+    unit_registry: UnitRegistry,
+    tech_registry: TechRegistry,
+    textures: Res<TextureAssets>,
+    mut researchcompleted_evw: EventWriter<ResearchCompleted>,
+) {
+    if !commander.think_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(entity) = enemy_castle.0 else {
+        return;
+    };
+    let Ok((
+        transform,
+        mut spawn_queue,
+        mut spawn_cooldown_reduction,
+        mut shield,
+        mut attack_cooldown,
+        mut tower_slots,
+        mut research,
+    )) = castles.get_mut(entity)
+    else {
+        return;
+    };
+    let tech_tree = tech_registry.get();
+
+    let player_army = army(&units, Faction::Ally);
+    let own_army = army(&units, Faction::Enemy);
+    let player_strength: usize = player_army.values().sum();
+    let own_strength = own_army.values().sum::<usize>() + spawn_queue.len();
+
+    /* Outnumbered, so units come first no matter the strategy */
+    let mut priorities = commander.priorities();
+    if player_strength > own_strength * 3 / 2 {
+        priorities.retain(|spending| *spending != Spending::Units);
+        priorities.insert(0, Spending::Units);
+    }
+
+    for spending in priorities {
+        let purchase = match spending {
+            Spending::Units => {
+                if spawn_queue.len() >= commander.parameters.max_queued {
+                    continue;
+                }
+                let counter_chance = commander.parameters.counter_chance;
+                let preferred = if counter_chance > 0.0 && rng.gen_bool(counter_chance as f64) {
+                    let player_towers = player_towers
+                        .iter()
+                        .filter(|faction| **faction == Faction::Ally)
+                        .count();
+                    counter(&player_army, player_towers)
+                } else {
+                    commander.strategy.unit()
+                };
+                /* Soldiers need no research */
+                let kind = if tech_tree.is_unit_unlocked(preferred, &research) {
+                    preferred
+                } else {
+                    UnitKind::Soldier
+                };
+                (Purchase::Unit(kind), unit_registry.get(&kind).cost)
+            }
+            Spending::Upgrades => {
+                let Some(purchase) = commander
+                    .strategy
+                    .upgrades()
+                    .into_iter()
+                    .filter_map(|upgrade| {
+                        let (maxed, cost) = match upgrade {
+                            EnemyUpgrade::SpawnCooldownReduction => (
+                                spawn_cooldown_reduction.is_max_level(),
+                                spawn_cooldown_reduction.cost(),
+                            ),
+                            EnemyUpgrade::Shield => (shield.is_max_level(), shield.cost()),
+                            EnemyUpgrade::AttackCooldown => {
+                                (attack_cooldown.is_max_level(), attack_cooldown.cost())
+                            }
+                        };
+                        (!maxed).then_some((Purchase::Upgrade(upgrade), cost))
+                    })
+                    .min_by_key(|(_, cost)| *cost)
+                else {
+                    continue;
+                };
+                purchase
+            }
+            Spending::Research => {
+                let Some(node) = (0..tech_tree.nodes().len())
+                    .find(|node| tech_tree.status(*node, &research) == TechStatus::Available)
+                else {
+                    continue;
+                };
+                (Purchase::Research(node), tech_tree.nodes()[node].cost)
+            }
+            Spending::Towers => {
+                let Some(slot) = tower_slots.free_slot(&towers) else {
+                    continue;
+                };
+                (Purchase::Tower(slot), Tower::COST)
+            }
+        };
+
+        /* The most important purchase is not affordable yet, save up for it */
+        let (purchase, cost) = purchase;
+        if commander.gold < cost {
+            break;
+        }
+
+        debug!("Enemy commander buys {:?} for {}", purchase, cost);
+        commander.spend(spending, cost);
+        match purchase {
+            Purchase::Unit(kind) => spawn_queue.push_back(kind),
+            Purchase::Upgrade(EnemyUpgrade::SpawnCooldownReduction) => {
+                spawn_cooldown_reduction.level_up();
+            }
+            Purchase::Upgrade(EnemyUpgrade::Shield) => {
+                shield.level_up();
+            }
+            Purchase::Upgrade(EnemyUpgrade::AttackCooldown) => {
+                attack_cooldown.level_up();
+            }
+            Purchase::Research(node) => {
+                research.insert(node);
+                researchcompleted_evw.send(ResearchCompleted {
+                    castle: entity,
+                    faction: Faction::Enemy,
+                    node,
+                });
+            }
+            Purchase::Tower(slot) => {
+                let tower = spawn_tower(
+                    &mut commands,
+                    Faction::Enemy,
+                    transform.translation().truncate(),
+                    slot,
+                    &textures,
+                );
+                tower_slots.insert(slot, tower);
+            }
+        }
+        break;
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    loading::{DataAssets, RonAsset},
+    session::MatchSetupSet,
+    GameState,
+};
+
+use super::{AllyCastle, Castle, Gold};
+
+pub struct EconomyPlugin;
+
+/// This plugin handles the gold of the player: spending it through the [`Treasury`], passive income, economic buildings and interest on banked gold
+/// Economy logic is only active during the State `GameState::Playing`
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GoldTransaction>()
+            .add_event::<PurchaseRejected>()
+            .init_resource::<IncomeTimer>()
+            .init_resource::<GoldLedger>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (reset_income_timer, reset_gold_ledger).in_set(MatchSetupSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (process_build_commands, pay_income)
+                    .chain()
+                    .in_set(SimulationSet::Commands)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Passive income, interest and economic buildings
+/// Loaded from `assets/data/game.economy.ron`
+#[derive(Debug, Asset, TypePath, Deserialize)]
+pub struct Economy {
+    pub income: IncomeDefinition,
+    pub interest: InterestDefinition,
+    buildings: Vec<BuildingDefinition>,
+}
+
+impl RonAsset for Economy {
+    const EXTENSIONS: &'static [&'static str] = &["economy.ron"];
+}
+
+impl Economy {
+    pub fn buildings(&self) -> &[BuildingDefinition] {
+        &self.buildings
+    }
+
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.buildings.iter().position(|building| building.id == id)
+    }
+
+    /// Gold of a single income tick without interest
+    pub fn income(&self, buildings: &Buildings) -> usize {
+        self.income.gold
+            + self
+                .buildings
+                .iter()
+                .enumerate()
+                .map(|(index, building)| building.income * buildings.count(index))
+                .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IncomeDefinition {
+    /// Seconds between two income ticks
+    pub interval: f32,
+    /// Gold of every income tick, before buildings and interest
+    pub gold: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InterestDefinition {
+    /// Share of the banked gold paid on every income tick
+    pub rate: f32,
+    /// Interest of a single income tick is capped at this
+    pub max: usize,
+}
+
+impl InterestDefinition {
+    pub fn get(&self, banked: usize) -> usize {
+        ((banked as f32 * self.rate).floor() as usize).min(self.max)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildingDefinition {
+    /// Referenced by save games
+    pub id: String,
+    pub name: String,
+    /// Cost of the first building of this kind
+    pub cost: usize,
+    /// Every further building of this kind costs this much more
+    pub cost_increase: usize,
+    /// Gold the building adds to every income tick
+    pub income: usize,
+    /// A castle can not have more buildings of this kind
+    pub max: usize,
+}
+
+impl BuildingDefinition {
+    /// Cost of the next building, given how many of this kind are built already
+    pub fn cost(&self, built: usize) -> usize {
+        self.cost + built * self.cost_increase
+    }
+}
+
+/// Looks up the loaded [`Economy`]
+#[derive(SystemParam)]
+pub struct EconomyRegistry<'w> {
+    data_assets: Res<'w, DataAssets>,
+    economies: Res<'w, Assets<Economy>>,
+}
+
+impl EconomyRegistry<'_> {
+    pub fn get(&self) -> &Economy {
+        self.economies
+            .get(&self.data_assets.economy)
+            .expect("economy.ron is loaded")
+    }
+}
+
+/// Economic buildings of a castle, by index into [`Economy::buildings`]
+#[derive(Debug, Default, Component)]
+pub struct Buildings(HashMap<usize, usize>);
+
+impl Buildings {
+    pub fn count(&self, index: usize) -> usize {
+        self.0.get(&index).copied().unwrap_or_default()
+    }
+
+    pub fn add(&mut self, index: usize, count: usize) {
+        *self.0.entry(index).or_default() += count;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.0.iter().map(|(index, count)| (*index, *count))
+    }
+}
+
+/// Time until the next income tick
+#[derive(Debug, Default, Resource)]
+pub struct IncomeTimer(pub Timer);
+
+/// What gold was spent on or earned with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GoldCategory {
+    Units,
+    Upgrades,
+    Towers,
+    Research,
+    Buildings,
+    /// Passive income and economic buildings
+    Income,
+    /// Interest on the banked gold
+    Interest,
+    /// Enemy units that were killed
+    Bounties,
+}
+
+impl GoldCategory {
+    pub const ALL: [Self; 8] = [
+        Self::Units,
+        Self::Upgrades,
+        Self::Towers,
+        Self::Research,
+        Self::Buildings,
+        Self::Income,
+        Self::Interest,
+        Self::Bounties,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    Earned,
+    Spent,
+    Refunded,
+}
+
+/// The gold of the player changed
+#[derive(Debug, Event)]
+pub struct GoldTransaction {
+    pub kind: TransactionKind,
+    pub amount: usize,
+    pub category: GoldCategory,
+    /// World position the gold was earned at, `None` if it was not earned on the battlefield
+    pub position: Option<Vec2>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// This much gold is missing
+    NotEnoughGold(usize),
+    MaxLevel,
+    /// Has to be researched first
+    Locked,
+    /// Every tower slot or place in the spawn queue is taken
+    NoSpace,
+    /// The queued unit to cancel has spawned already
+    AlreadySpawned,
+}
+
+/// A purchase of the player was not made
+#[derive(Debug, Event)]
+pub struct PurchaseRejected {
+    pub category: GoldCategory,
+    pub reason: RejectionReason,
+}
+
+/// Gold the player earned and spent during the current match, refunds are taken off the spendings
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct GoldLedger {
+    earned: HashMap<GoldCategory, usize>,
+    spent: HashMap<GoldCategory, usize>,
+}
+
+impl GoldLedger {
+    pub fn earned(&self, category: GoldCategory) -> usize {
+        self.earned.get(&category).copied().unwrap_or_default()
+    }
+
+    pub fn spent(&self, category: GoldCategory) -> usize {
+        self.spent.get(&category).copied().unwrap_or_default()
+    }
+
+    pub fn total_earned(&self) -> usize {
+        self.earned.values().sum()
+    }
+
+    pub fn total_spent(&self) -> usize {
+        self.spent.values().sum()
+    }
+}
+
+/// The only way to change the gold of the player during a match
+/// Every change is recorded in the [`GoldLedger`] and reported as a [`GoldTransaction`]
+#[derive(SystemParam)]
+pub struct Treasury<'w> {
+    gold: ResMut<'w, Gold>,
+    ledger: ResMut<'w, GoldLedger>,
+    goldtransaction_evw: EventWriter<'w, GoldTransaction>,
+    purchaserejected_evw: EventWriter<'w, PurchaseRejected>,
+}
+
+impl Treasury<'_> {
+    pub fn gold(&self) -> usize {
+        self.gold.0
+    }
+
+    /// Takes `cost` if the player can afford it, otherwise the purchase is rejected
+    pub fn try_spend(&mut self, cost: usize, category: GoldCategory) -> bool {
+        if self.gold.0 < cost {
+            self.reject(category, RejectionReason::NotEnoughGold(cost - self.gold.0));
+            return false;
+        }
+
+        self.gold.0 -= cost;
+        *self.ledger.spent.entry(category).or_default() += cost;
+        self.goldtransaction_evw.send(GoldTransaction {
+            kind: TransactionKind::Spent,
+            amount: cost,
+            category,
+            position: None,
+        });
+        true
+    }
+
+    /// Gives back gold of a purchase that was undone
+    pub fn refund(&mut self, amount: usize, category: GoldCategory) {
+        self.gold.0 += amount;
+        let spent = self.ledger.spent.entry(category).or_default();
+        *spent = spent.saturating_sub(amount);
+        self.goldtransaction_evw.send(GoldTransaction {
+            kind: TransactionKind::Refunded,
+            amount,
+            category,
+            position: None,
+        });
+    }
+
+    pub fn earn(&mut self, amount: usize, category: GoldCategory, position: Option<Vec2>) {
+        if amount == 0 {
+            return;
+        }
+
+        self.gold.0 += amount;
+        *self.ledger.earned.entry(category).or_default() += amount;
+        self.goldtransaction_evw.send(GoldTransaction {
+            kind: TransactionKind::Earned,
+            amount,
+            category,
+            position,
+        });
+    }
+
+    /// Rejects a purchase for a reason other than its cost
+    pub fn reject(&mut self, category: GoldCategory, reason: RejectionReason) {
+        self.purchaserejected_evw
+            .send(PurchaseRejected { category, reason });
+    }
+}
+
+fn reset_gold_ledger(mut ledger: ResMut<GoldLedger>) {
+    *ledger = GoldLedger::default();
+}
+
+fn reset_income_timer(mut income_timer: ResMut<IncomeTimer>, economy_registry: EconomyRegistry) {
+    income_timer.0 =
+        Timer::from_seconds(economy_registry.get().income.interval, TimerMode::Repeating);
+}
+
+fn process_build_commands(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut castles: Query<&mut Buildings, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+    economy_registry: EconomyRegistry,
+) {
+    for ev in playercommand_evr.read() {
+        let PlayerAction::Build(index) = ev.action else {
+            continue;
+        };
+        let Some(mut buildings) = ally_castle
+            .0
+            .and_then(|entity| castles.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        let Some(building) = economy_registry.get().buildings().get(index) else {
+            continue;
+        };
+
+        let built = buildings.count(index);
+        if built >= building.max {
+            treasury.reject(GoldCategory::Buildings, RejectionReason::MaxLevel);
+        } else if treasury.try_spend(building.cost(built), GoldCategory::Buildings) {
+            buildings.add(index, 1);
+        }
+    }
+}
+
+fn pay_income(
+    time: Res<Time>,
+    mut income_timer: ResMut<IncomeTimer>,
+    castles: Query<&Buildings, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+    economy_registry: EconomyRegistry,
+) {
+    if !income_timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(buildings) = ally_castle.0.and_then(|entity| castles.get(entity).ok()) else {
+        return;
+    };
+    let economy = economy_registry.get();
+
+    /* Interest is paid on the gold banked before this tick */
+    let interest = economy.interest.get(treasury.gold());
+    treasury.earn(economy.income(buildings), GoldCategory::Income, None);
+    treasury.earn(interest, GoldCategory::Interest, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{event::Events, system::SystemState};
+
+    use super::*;
+
+    fn treasury(gold: usize) -> (World, SystemState<Treasury<'static>>) {
+        let mut world = World::new();
+        world.insert_resource(Gold(gold));
+        world.init_resource::<GoldLedger>();
+        world.init_resource::<Events<GoldTransaction>>();
+        world.init_resource::<Events<PurchaseRejected>>();
+        let state = SystemState::new(&mut world);
+        (world, state)
+    }
+
+    #[test]
+    fn spending_the_exact_gold_succeeds() {
+        let (mut world, mut state) = treasury(10);
+        let mut treasury = state.get_mut(&mut world);
+
+        assert!(treasury.try_spend(10, GoldCategory::Units));
+        assert_eq!(treasury.gold(), 0);
+        assert_eq!(treasury.ledger.spent(GoldCategory::Units), 10);
+
+        let transactions = world.resource::<Events<GoldTransaction>>();
+        assert_eq!(transactions.len(), 1);
+    }
+
+    #[test]
+    fn spending_more_than_the_gold_is_rejected() {
+        let (mut world, mut state) = treasury(10);
+        let mut treasury = state.get_mut(&mut world);
+
+        assert!(!treasury.try_spend(11, GoldCategory::Towers));
+        assert_eq!(treasury.gold(), 10);
+        assert_eq!(treasury.ledger.total_spent(), 0);
+
+        let rejections = world.resource::<Events<PurchaseRejected>>();
+        assert_eq!(
+            rejections
+                .iter_current_update_events()
+                .map(|ev| (ev.category, ev.reason))
+                .collect::<Vec<_>>(),
+            vec![(GoldCategory::Towers, RejectionReason::NotEnoughGold(1))]
+        );
+        assert!(world.resource::<Events<GoldTransaction>>().is_empty());
+    }
+
+    #[test]
+    fn refunds_are_taken_off_the_spendings() {
+        let (mut world, mut state) = treasury(10);
+        let mut treasury = state.get_mut(&mut world);
+
+        treasury.try_spend(10, GoldCategory::Units);
+        treasury.refund(4, GoldCategory::Units);
+        assert_eq!(treasury.gold(), 4);
+        assert_eq!(treasury.ledger.spent(GoldCategory::Units), 6);
+
+        /* More than was spent still gives the gold back, but the spendings do not go below zero */
+        treasury.refund(8, GoldCategory::Units);
+        assert_eq!(treasury.gold(), 12);
+        assert_eq!(treasury.ledger.spent(GoldCategory::Units), 0);
+    }
+
+    #[test]
+    fn earning_nothing_is_not_recorded() {
+        let (mut world, mut state) = treasury(5);
+        let mut treasury = state.get_mut(&mut world);
+
+        treasury.earn(0, GoldCategory::Interest, None);
+        assert_eq!(treasury.gold(), 5);
+        assert_eq!(treasury.ledger.total_earned(), 0);
+        assert!(world.resource::<Events<GoldTransaction>>().is_empty());
+    }
+
+    #[test]
+    fn totals_add_up_the_categories() {
+        let mut ledger = GoldLedger::default();
+        ledger.earned.insert(GoldCategory::Income, 7);
+        ledger.earned.insert(GoldCategory::Bounties, 3);
+        ledger.spent.insert(GoldCategory::Towers, 5);
+        ledger.spent.insert(GoldCategory::Research, 2);
+
+        assert_eq!(ledger.earned(GoldCategory::Interest), 0);
+        assert_eq!(ledger.total_earned(), 10);
+        assert_eq!(ledger.total_spent(), 7);
+    }
+
+    #[test]
+    fn rejections_do_not_touch_the_gold() {
+        let (mut world, mut state) = treasury(10);
+        let mut treasury = state.get_mut(&mut world);
+
+        treasury.reject(GoldCategory::Upgrades, RejectionReason::MaxLevel);
+        assert_eq!(treasury.gold(), 10);
+        assert!(world.resource::<Events<GoldTransaction>>().is_empty());
+        assert_eq!(world.resource::<Events<PurchaseRejected>>().len(), 1);
+    }
+}
@@ -1,14 +1,23 @@
+pub mod commander;
+pub mod economy;
 pub mod spawner;
+pub mod tech;
+pub mod tower;
 pub mod upgrade;
 
 use std::collections::VecDeque;
+use std::time::Duration;
 
+use crate::click::ClickDamageUpgrade;
+use crate::command::{PendingPlayerActions, PlayerAction, PlayerCommand, SimulationSet};
 use crate::common::attributes::{Health, Immortal};
 use crate::common::Faction;
 use crate::loading::TextureAssets;
 use crate::physics::hit_detection::HurtBoxBundle;
 use crate::physics::PhysicsCollisionBundle;
-use crate::units::upgrade::{AttackCooldownUpgrade, ShieldUpgrade};
+use crate::session::{MatchEntity, MatchSettings, MatchSetupSet};
+use crate::units::definition::UnitRegistry;
+use crate::units::upgrade::{AttackCooldownUpgrade, ShieldUpgrade, UnitModifiers};
 use crate::units::UnitKind;
 use crate::GameState;
 use bevy::input::keyboard::KeyboardInput;
@@ -16,7 +25,11 @@ use bevy::input::ButtonState;
 use bevy::prelude::*;
 use bevy_xpbd_2d::components::{Collider, CollisionLayers, RigidBody};
 
+use self::commander::CommanderPlugin;
+use self::economy::{Buildings, EconomyPlugin, GoldCategory, RejectionReason, Treasury};
 use self::spawner::SpawnerPlugin;
+use self::tech::{Research, TechPlugin, TechRegistry};
+use self::tower::{TowerPlugin, TowerSlots};
 use self::upgrade::{SpawnCooldownReduction, UpgradePlugin};
 
 pub struct CastlePlugin;
@@ -34,27 +47,38 @@ pub struct EnemyCastle(pub Option<Entity>);
 /// Castle logic is only active during the State `GameState::Playing`
 impl Plugin for CastlePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((SpawnerPlugin, UpgradePlugin))
-            .init_resource::<AllyCastle>()
-            .init_resource::<EnemyCastle>()
-            .init_resource::<Gold>()
-            .add_event::<SpawnUnit>()
-            .add_event::<QueueAllyUnit>()
-            .add_systems(
-                OnEnter(GameState::Playing),
-                (spawn_ally_castle, spawn_enemy_castle, init_gold),
+        app.add_plugins((
+            CommanderPlugin,
+            EconomyPlugin,
+            SpawnerPlugin,
+            TechPlugin,
+            TowerPlugin,
+            UpgradePlugin,
+        ))
+        .init_resource::<AllyCastle>()
+        .init_resource::<EnemyCastle>()
+        .init_resource::<Gold>()
+        .add_event::<SpawnUnit>()
+        .add_systems(
+            OnEnter(GameState::Playing),
+            (spawn_ally_castle, spawn_enemy_castle, init_gold).in_set(MatchSetupSet),
+        )
+        .add_systems(
+            Update,
+            emit_queue_enemy_unit.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                /* Both change the spawn queue, so the order has to be the same in every replay */
+                (process_queue_ally_unit, process_cancel_queued_ally_unit)
+                    .chain()
+                    .in_set(SimulationSet::Commands),
+                spawn_queue.in_set(SimulationSet::Spawn),
+                (game_over, won).in_set(SimulationSet::Death),
             )
-            .add_systems(
-                Update,
-                (
-                    spawn_queue,
-                    process_queue_ally_unit,
-                    game_over,
-                    emit_queue_enemy_unit,
-                    won,
-                )
-                    .run_if(in_state(GameState::Playing)),
-            );
+                .run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
@@ -62,6 +86,7 @@ fn spawn_ally_castle(
     mut commands: Commands,
     textures: Res<TextureAssets>,
     mut ally_castle: ResMut<AllyCastle>,
+    settings: Res<MatchSettings>,
 ) {
     let entity = commands
         .spawn(SpriteBundle {
@@ -75,17 +100,23 @@ fn spawn_ally_castle(
         })
         .insert(Faction::Ally)
         .insert(Castle)
+        .insert(MatchEntity)
         .insert(Immortal)
-        .insert(Health::new(1000.0))
+        .insert(Health::new(settings.castle_health))
         .insert(PhysicsCollisionBundle {
             rigid_body: RigidBody::Static,
             collider: Collider::ball(128.0),
             ..Default::default()
         })
         .insert(SpawnQueue::default())
+        .insert(TowerSlots::default())
         .insert(SpawnCooldownReduction::default())
-        .insert(ShieldUpgrade::default())
-        .insert(AttackCooldownUpgrade::default())
+        .insert(ShieldUpgrade::default().with_scope(settings.shield_scope))
+        .insert(AttackCooldownUpgrade::default().with_scope(settings.attack_cooldown_scope))
+        .insert(UnitModifiers::default())
+        .insert(Research::default())
+        .insert(Buildings::default())
+        .insert(ClickDamageUpgrade::default())
         .with_children(|children| {
             children.spawn(HurtBoxBundle {
                 collider: Collider::ball(127.0),
@@ -105,6 +136,7 @@ fn spawn_enemy_castle(
     mut commands: Commands,
     textures: Res<TextureAssets>,
     mut enemy_castle: ResMut<EnemyCastle>,
+    settings: Res<MatchSettings>,
 ) {
     let entity = commands
         .spawn(SpriteBundle {
@@ -118,17 +150,26 @@ fn spawn_enemy_castle(
         })
         .insert(Faction::Enemy)
         .insert(Castle)
+        .insert(MatchEntity)
         .insert(Immortal)
-        .insert(Health::new(1000.0))
+        .insert(Health::new(settings.castle_health))
         .insert(PhysicsCollisionBundle {
             rigid_body: RigidBody::Static,
             collider: Collider::ball(128.0),
             ..Default::default()
         })
         .insert(SpawnQueue::default())
-        .insert(SpawnCooldownReduction::new(2))
-        .insert(ShieldUpgrade::new(2))
-        .insert(AttackCooldownUpgrade::new(2))
+        .insert(TowerSlots::default())
+        .insert(SpawnCooldownReduction::new(settings.enemy_upgrade_level))
+        .insert(ShieldUpgrade::new(settings.enemy_upgrade_level).with_scope(settings.shield_scope))
+        .insert(
+            AttackCooldownUpgrade::new(settings.enemy_upgrade_level)
+                .with_scope(settings.attack_cooldown_scope),
+        )
+        .insert(UnitModifiers::default())
+        .insert(Research::default())
+        .insert(Buildings::default())
+        .insert(ClickDamageUpgrade::default())
         .with_children(|children| {
             children.spawn(HurtBoxBundle {
                 collider: Collider::ball(127.0),
@@ -147,29 +188,98 @@ fn spawn_enemy_castle(
 #[derive(Debug, Component)]
 pub struct SpawnQueue {
     pub timer: Timer,
-    pub units: VecDeque<UnitKind>,
+    /// Seconds between two units, before `SpawnCooldownReduction`
+    pub interval: f32,
+    units: VecDeque<QueuedUnit>,
+    /// Id of the next queued unit
+    next_id: u32,
 }
 
 impl Default for SpawnQueue {
     fn default() -> Self {
         Self {
             timer: Timer::from_seconds(2.0, TimerMode::Repeating),
+            interval: 2.0,
             units: Default::default(),
+            next_id: 0,
         }
     }
 }
 
-fn spawn_queue(
+/// A unit waiting in a [`SpawnQueue`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuedUnit {
+    /// Stays the same while the units in front of it spawn or are cancelled
+    pub id: u32,
+    pub kind: UnitKind,
+}
+
+impl SpawnQueue {
+    /// The player can not queue more units than this, the enemy commander is limited by its `max_queued`
+    pub const MAX_LENGTH: usize = 20;
+
+    pub fn is_full(&self) -> bool {
+        self.units.len() >= Self::MAX_LENGTH
+    }
+
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Queued units in the order they spawn
+    pub fn iter(&self) -> impl Iterator<Item = &QueuedUnit> {
+        self.units.iter()
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = UnitKind> + '_ {
+        self.units.iter().map(|unit| unit.kind)
+    }
+
+    pub fn push_back(&mut self, kind: UnitKind) {
+        self.units.push_back(QueuedUnit {
+            id: self.next_id,
+            kind,
+        });
+        self.next_id += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<UnitKind> {
+        self.units.pop_front().map(|unit| unit.kind)
+    }
+
+    /// Removes the unit with this id, `None` if it is not queued anymore
+    pub fn cancel(&mut self, id: u32) -> Option<UnitKind> {
+        let index = self.units.iter().position(|unit| unit.id == id)?;
+        self.units.remove(index).map(|unit| unit.kind)
+    }
+
+    pub fn set_interval(
+        &mut self,
+        interval: f32,
+        spawn_cooldown_reduction: &SpawnCooldownReduction,
+    ) {
+        self.interval = interval;
+        self.timer.set_duration(Duration::from_secs_f32(
+            interval * (1.0 - spawn_cooldown_reduction.get()),
+        ));
+    }
+}
+
+pub fn spawn_queue(
     time: Res<Time>,
     mut query: Query<(Entity, &mut SpawnQueue, &Faction)>,
     mut spawnunit_evw: EventWriter<SpawnUnit>,
 ) {
     for (origin, mut spawn_queue, faction) in &mut query {
-        if !spawn_queue.units.is_empty() && spawn_queue.timer.tick(time.delta()).just_finished() {
+        if !spawn_queue.is_empty() && spawn_queue.timer.tick(time.delta()).just_finished() {
             spawnunit_evw.send(SpawnUnit {
                 origin,
                 faction: *faction,
-                kind: spawn_queue.units.pop_front().unwrap(),
+                kind: spawn_queue.pop_front().unwrap(),
             });
         }
     }
@@ -183,23 +293,70 @@ pub struct SpawnUnit {
 }
 
 fn process_queue_ally_unit(
-    mut queueallyunit_evr: EventReader<QueueAllyUnit>,
-    mut spawn_queue: Query<&mut SpawnQueue>,
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut spawn_queue: Query<(&mut SpawnQueue, &Research)>,
     ally_castle: Res<AllyCastle>,
-    mut gold: ResMut<Gold>,
+    mut treasury: Treasury,
+    unit_registry: UnitRegistry,
+    tech_registry: TechRegistry,
 ) {
-    for ev in queueallyunit_evr.read() {
-        if gold.0 > 0 {
-            if let Some(entity) = ally_castle.0 {
-                if let Ok(mut spawn_queue) = spawn_queue.get_mut(entity) {
-                    spawn_queue.units.push_back(ev.kind);
-                    gold.0 -= ev.kind.cost();
+    for ev in playercommand_evr.read() {
+        let PlayerAction::QueueUnit(kind) = ev.action else {
+            continue;
+        };
+
+        if let Some(entity) = ally_castle.0 {
+            if let Ok((mut spawn_queue, research)) = spawn_queue.get_mut(entity) {
+                if !tech_registry.get().is_unit_unlocked(kind, research) {
+                    treasury.reject(GoldCategory::Units, RejectionReason::Locked);
+                } else if spawn_queue.is_full() {
+                    treasury.reject(GoldCategory::Units, RejectionReason::NoSpace);
+                } else if treasury.try_spend(unit_registry.get(&kind).cost, GoldCategory::Units) {
+                    spawn_queue.push_back(kind);
                 }
             }
         }
     }
 }
 
+fn process_cancel_queued_ally_unit(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut spawn_queues: Query<&mut SpawnQueue>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+    unit_registry: UnitRegistry,
+) {
+    for ev in playercommand_evr.read() {
+        let PlayerAction::CancelQueuedUnit(id) = ev.action else {
+            continue;
+        };
+        let Some(mut spawn_queue) = ally_castle
+            .0
+            .and_then(|entity| spawn_queues.get_mut(entity).ok())
+        else {
+            continue;
+        };
+
+        cancel_queued_unit(&mut spawn_queue, id, &mut treasury, |kind| {
+            unit_registry.get(&kind).cost
+        });
+    }
+}
+
+/// Refunds the unit with this id if it is still queued
+fn cancel_queued_unit(
+    spawn_queue: &mut SpawnQueue,
+    id: u32,
+    treasury: &mut Treasury,
+    cost: impl FnOnce(UnitKind) -> usize,
+) {
+    /* The unit might have spawned since the player clicked it */
+    match spawn_queue.cancel(id) {
+        Some(kind) => treasury.refund(cost(kind), GoldCategory::Units),
+        None => treasury.reject(GoldCategory::Units, RejectionReason::AlreadySpawned),
+    }
+}
+
 fn game_over(
     health: Query<&Health>,
     ally_castle: Res<AllyCastle>,
@@ -231,26 +388,93 @@ fn won(
 #[derive(Debug, Default, Resource)]
 pub struct Gold(pub usize);
 
-fn init_gold(mut gold: ResMut<Gold>) {
-    gold.0 = 60;
-}
-
-#[derive(Debug, Event)]
-pub struct QueueAllyUnit {
-    pub kind: UnitKind,
+fn init_gold(mut gold: ResMut<Gold>, settings: Res<MatchSettings>) {
+    gold.0 = settings.starting_gold;
 }
 
 fn emit_queue_enemy_unit(
     mut keyboard_evr: EventReader<KeyboardInput>,
-    mut queueunit_evw: EventWriter<QueueAllyUnit>,
+    mut player_actions: ResMut<PendingPlayerActions>,
 ) {
     for ev in keyboard_evr.read() {
         if let Some(KeyCode::Space) = ev.key_code {
             if ev.state == ButtonState::Pressed {
-                queueunit_evw.send(QueueAllyUnit {
-                    kind: UnitKind::Soldier,
-                });
+                player_actions.send(PlayerAction::QueueUnit(UnitKind::Soldier));
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{event::Events, system::SystemState};
+
+    use super::economy::{GoldLedger, GoldTransaction, PurchaseRejected};
+    use super::*;
+
+    fn cost(kind: UnitKind) -> usize {
+        match kind {
+            UnitKind::Soldier => 10,
+            UnitKind::Archer => 15,
+            UnitKind::Knight => 30,
+        }
+    }
+
+    #[test]
+    fn cancelling_refunds_queued_units_and_rejects_spawned_ones() {
+        let mut world = World::new();
+        world.insert_resource(Gold(100));
+        world.init_resource::<GoldLedger>();
+        world.init_resource::<Events<GoldTransaction>>();
+        world.init_resource::<Events<PurchaseRejected>>();
+        let mut state = SystemState::<Treasury>::new(&mut world);
+        let mut treasury = state.get_mut(&mut world);
+
+        let mut spawn_queue = SpawnQueue::default();
+        for kind in [
+            UnitKind::Soldier,
+            UnitKind::Archer,
+            UnitKind::Knight,
+            UnitKind::Soldier,
+        ] {
+            assert!(treasury.try_spend(cost(kind), GoldCategory::Units));
+            spawn_queue.push_back(kind);
+        }
+        assert_eq!(spawn_queue.pop_front(), Some(UnitKind::Soldier));
+
+        /* The knight is in the middle of the queue */
+        cancel_queued_unit(&mut spawn_queue, 2, &mut treasury, cost);
+        assert_eq!(
+            spawn_queue.iter().copied().collect::<Vec<_>>(),
+            vec![
+                QueuedUnit {
+                    id: 1,
+                    kind: UnitKind::Archer,
+                },
+                QueuedUnit {
+                    id: 3,
+                    kind: UnitKind::Soldier,
+                },
+            ]
+        );
+        assert_eq!(treasury.gold(), 65);
+
+        /* The first soldier has spawned already */
+        cancel_queued_unit(&mut spawn_queue, 0, &mut treasury, cost);
+        assert_eq!(spawn_queue.len(), 2);
+        assert_eq!(treasury.gold(), 65);
+
+        assert_eq!(
+            world.resource::<GoldLedger>().spent(GoldCategory::Units),
+            35
+        );
+        assert_eq!(
+            world
+                .resource::<Events<PurchaseRejected>>()
+                .iter_current_update_events()
+                .map(|ev| (ev.category, ev.reason))
+                .collect::<Vec<_>>(),
+            vec![(GoldCategory::Units, RejectionReason::AlreadySpawned)]
+        );
+    }
+}
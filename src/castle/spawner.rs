@@ -1,46 +1,166 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 use crate::{
     castle::{EnemyCastle, SpawnQueue},
-    units::{
-        upgrade::{AttackCooldownUpgrade, ShieldUpgrade},
-        UnitKind,
-    },
+    command::SimulationSet,
+    loading::{DataAssets, RonAsset},
+    session::{MatchSettings, MatchSetupSet},
+    units::upgrade::{AttackCooldownUpgrade, ShieldUpgrade},
     GameState,
 };
 
-use super::upgrade::SpawnCooldownReduction;
+use super::{commander::Commander, upgrade::SpawnCooldownReduction};
 pub struct SpawnerPlugin;
 
 // This plugin is responsible to control the game audio
 impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Wave>()
-            .add_systems(Update, tick_wave_timer.run_if(in_state(GameState::Playing)));
+            .add_systems(OnEnter(GameState::Playing), init_wave.in_set(MatchSetupSet))
+            .add_systems(
+                FixedUpdate,
+                tick_wave_timer
+                    .in_set(SimulationSet::Commands)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
+/// Scripted enemy waves, loaded from `assets/data/game.waves.ron`
+/// Every wave pays the enemy commander, who decides what to spend the gold on
+/// After the last scripted wave, waves are generated by `endless`
+#[derive(Debug, Asset, TypePath, Deserialize)]
+pub struct WaveSchedule {
+    waves: Vec<WaveDefinition>,
+    endless: EndlessWaves,
+}
+
+impl RonAsset for WaveSchedule {
+    const EXTENSIONS: &'static [&'static str] = &["waves.ron"];
+}
+
+impl WaveSchedule {
+    /// Definition of the wave with the given level, starting at 1
+    pub fn get(&self, level: u32) -> WaveDefinition {
+        self.waves
+            .get(level.saturating_sub(1) as usize)
+            .cloned()
+            .unwrap_or_else(|| self.endless.generate(level))
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct WaveDefinition {
+    /// Seconds to wait after the previous wave started
+    pub delay: f32,
+    /// Seconds between two units, before `SpawnCooldownReduction`
+    pub interval: f32,
+    /// Gold the enemy commander receives when the wave starts
+    pub gold: usize,
+    /// Upgrades the enemy castle receives when the wave starts
+    pub upgrades: Vec<EnemyUpgrade>,
+}
+
+impl WaveDefinition {
+    /// Applies the wave factors of the [`MatchSettings`]
+    fn scaled(mut self, settings: &MatchSettings) -> Self {
+        self.delay *= settings.wave_delay;
+        self.gold = (self.gold as f32 * settings.wave_gold).round() as usize;
+        self
+    }
+}
+
+impl std::fmt::Display for WaveDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "+{} enemy gold", self.gold)
+    }
+}
+
+/// Procedural waves for endless play
+#[derive(Debug, Deserialize)]
+pub struct EndlessWaves {
+    pub delay: f32,
+    pub interval: f32,
+    /// Gold per wave level
+    pub gold: usize,
+    /// Applied on every wave whose level is a multiple of `upgrade_every`
+    pub upgrades: Vec<EnemyUpgrade>,
+    pub upgrade_every: u32,
+}
+
+impl EndlessWaves {
+    fn generate(&self, level: u32) -> WaveDefinition {
+        WaveDefinition {
+            delay: self.delay,
+            interval: self.interval,
+            gold: self.gold * level as usize,
+            upgrades: if level % self.upgrade_every.max(1) == 0 {
+                self.upgrades.clone()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum EnemyUpgrade {
+    SpawnCooldownReduction,
+    Shield,
+    AttackCooldown,
+}
+
 #[derive(Debug, Resource)]
 pub struct Wave {
     pub level: u32,
+    /// Delay until the current wave starts
     pub timer: Timer,
+    /// Definition of the wave that starts next
+    pub current: WaveDefinition,
 }
 
 impl Default for Wave {
     fn default() -> Self {
         Self {
             level: 1,
-            timer: Timer::from_seconds(5.0, TimerMode::Repeating),
+            timer: Timer::from_seconds(5.0, TimerMode::Once),
+            current: WaveDefinition::default(),
         }
     }
 }
 
+impl Wave {
+    pub fn set_level(
+        &mut self,
+        level: u32,
+        wave_schedule: &WaveSchedule,
+        settings: &MatchSettings,
+    ) {
+        self.level = level;
+        self.current = wave_schedule.get(level).scaled(settings);
+        self.timer = Timer::from_seconds(self.current.delay, TimerMode::Once);
+    }
+}
+
 #[derive(Debug, Default, Component)]
 struct Spawner;
 
-fn tick_wave_timer(
+fn init_wave(
+    mut wave: ResMut<Wave>,
+    data_assets: Res<DataAssets>,
+    wave_schedules: Res<Assets<WaveSchedule>>,
+    settings: Res<MatchSettings>,
+) {
+    if let Some(wave_schedule) = wave_schedules.get(&data_assets.waves) {
+        wave.set_level(1, wave_schedule, &settings);
+    }
+}
+
+pub fn tick_wave_timer(
     time: Res<Time>,
     mut wave: ResMut<Wave>,
+    mut commander: ResMut<Commander>,
     enemy_castle: Res<EnemyCastle>,
     mut spawn_queue: Query<(
         &mut SpawnQueue,
@@ -48,26 +168,33 @@ fn tick_wave_timer(
         &mut ShieldUpgrade,
         &mut AttackCooldownUpgrade,
     )>,
+    data_assets: Res<DataAssets>,
+    wave_schedules: Res<Assets<WaveSchedule>>,
+    settings: Res<MatchSettings>,
 ) {
+    let Some(wave_schedule) = wave_schedules.get(&data_assets.waves) else {
+        return;
+    };
+
     if let Some(entity) = enemy_castle.0 {
         if let Ok((mut spawn_queue, mut spawn_cooldown, mut shield, mut attack_speed)) =
             spawn_queue.get_mut(entity)
         {
-            if spawn_queue.units.is_empty() && wave.timer.tick(time.delta()).just_finished() {
-                (0..30 * wave.level).for_each(|_| {
-                    spawn_queue.units.push_back(UnitKind::Soldier);
-                });
-
-                wave.level += 1;
-
-                if wave.level % 2 == 0 {
-                    spawn_cooldown.level_up();
-                    spawn_cooldown.level_up();
-                    shield.level_up();
-                    shield.level_up();
-                    attack_speed.level_up();
-                    attack_speed.level_up();
+            if wave.timer.tick(time.delta()).just_finished() {
+                commander.gold += wave.current.gold;
+
+                for upgrade in &wave.current.upgrades {
+                    match upgrade {
+                        EnemyUpgrade::SpawnCooldownReduction => spawn_cooldown.level_up(),
+                        EnemyUpgrade::Shield => shield.level_up(),
+                        EnemyUpgrade::AttackCooldown => attack_speed.level_up(),
+                    };
                 }
+
+                spawn_queue.set_interval(wave.current.interval, &spawn_cooldown);
+
+                let level = wave.level + 1;
+                wave.set_level(level, wave_schedule, &settings);
             }
         }
     }
@@ -0,0 +1,206 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    common::{
+        stats::{ModifierValue, Stat},
+        Faction,
+    },
+    loading::{DataAssets, RonAsset},
+    units::UnitKind,
+    GameState,
+};
+
+use super::{
+    economy::{GoldCategory, RejectionReason, Treasury},
+    AllyCastle, Castle,
+};
+
+pub struct TechPlugin;
+
+/// This plugin handles researching the nodes of the tech tree
+/// Tech logic is only active during the State `GameState::Playing`
+impl Plugin for TechPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResearchCompleted>().add_systems(
+            FixedUpdate,
+            process_research_commands
+                .in_set(SimulationSet::Commands)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Everything a castle can research
+/// Loaded from `assets/data/game.tech.ron`
+#[derive(Debug, Asset, TypePath, Deserialize)]
+pub struct TechTree {
+    nodes: Vec<TechNode>,
+}
+
+impl RonAsset for TechTree {
+    const EXTENSIONS: &'static [&'static str] = &["tech.ron"];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TechNode {
+    /// Referenced by `requires` and save games
+    pub id: String,
+    pub name: String,
+    pub cost: usize,
+    /// Ids of the nodes that have to be researched first
+    #[serde(default)]
+    pub requires: Vec<String>,
+    pub effects: Vec<TechEffect>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum TechEffect {
+    /// The unit kind can only be queued once a node with this effect is researched
+    UnlockUnit(UnitKind),
+    /// Modifies the stats of every unit of the castle, including the ones already on the battlefield
+    UnitModifier(Stat, ModifierValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TechStatus {
+    /// Some prerequisites are missing
+    Locked,
+    /// Can be researched
+    Available,
+    Researched,
+}
+
+impl TechTree {
+    pub fn nodes(&self) -> &[TechNode] {
+        &self.nodes
+    }
+
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.id == id)
+    }
+
+    pub fn status(&self, index: usize, research: &Research) -> TechStatus {
+        if research.is_researched(index) {
+            return TechStatus::Researched;
+        }
+
+        let prerequisites_met = self.nodes[index].requires.iter().all(|id| {
+            self.index_of(id)
+                .is_some_and(|index| research.is_researched(index))
+        });
+        if prerequisites_met {
+            TechStatus::Available
+        } else {
+            TechStatus::Locked
+        }
+    }
+
+    /// Units no node unlocks are always available
+    pub fn is_unit_unlocked(&self, kind: UnitKind, research: &Research) -> bool {
+        let mut unlocking_nodes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                node.effects.iter().any(
+                    |effect| matches!(effect, TechEffect::UnlockUnit(unlocked) if *unlocked == kind),
+                )
+            })
+            .map(|(index, _)| index)
+            .peekable();
+
+        unlocking_nodes.peek().is_none()
+            || unlocking_nodes.any(|index| research.is_researched(index))
+    }
+}
+
+/// Looks up the loaded [`TechTree`]
+#[derive(SystemParam)]
+pub struct TechRegistry<'w> {
+    data_assets: Res<'w, DataAssets>,
+    tech_trees: Res<'w, Assets<TechTree>>,
+}
+
+impl TechRegistry<'_> {
+    pub fn get(&self) -> &TechTree {
+        self.tech_trees
+            .get(&self.data_assets.tech)
+            .expect("tech.ron is loaded")
+    }
+}
+
+/// Nodes of the [`TechTree`] a castle has researched, in the order they were researched
+#[derive(Debug, Default, Component)]
+pub struct Research {
+    researched: Vec<usize>,
+}
+
+impl Research {
+    pub fn is_researched(&self, index: usize) -> bool {
+        self.researched.contains(&index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.researched.iter().copied()
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        if !self.is_researched(index) {
+            self.researched.push(index);
+        }
+    }
+}
+
+/// A castle finished researching a node of the [`TechTree`]
+/// Also sent for every researched node when a match is restored
+#[derive(Debug, Event)]
+pub struct ResearchCompleted {
+    pub castle: Entity,
+    pub faction: Faction,
+    /// Index into [`TechTree::nodes`]
+    pub node: usize,
+}
+
+pub fn process_research_commands(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut castles: Query<&mut Research, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+    tech_registry: TechRegistry,
+    mut researchcompleted_evw: EventWriter<ResearchCompleted>,
+) {
+    for ev in playercommand_evr.read() {
+        let PlayerAction::Research(node) = ev.action else {
+            continue;
+        };
+        let Some(entity) = ally_castle.0 else {
+            continue;
+        };
+        let Ok(mut research) = castles.get_mut(entity) else {
+            continue;
+        };
+
+        let tech_tree = tech_registry.get();
+        let Some(tech_node) = tech_tree.nodes().get(node) else {
+            continue;
+        };
+        match tech_tree.status(node, &research) {
+            TechStatus::Locked => {
+                treasury.reject(GoldCategory::Research, RejectionReason::Locked);
+                continue;
+            }
+            TechStatus::Researched => continue,
+            TechStatus::Available => {}
+        }
+        if treasury.try_spend(tech_node.cost, GoldCategory::Research) {
+            research.insert(node);
+            researchcompleted_evw.send(ResearchCompleted {
+                castle: entity,
+                faction: Faction::Ally,
+                node,
+            });
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::components::{
+    Collider, ColliderParent, CollidingEntities, CollisionLayers, RigidBody, Sensor,
+};
+
+use crate::{
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    common::{
+        attributes::{Health, HealthDeltaSource},
+        damage::{Damage, DamageType, DealDamage},
+        status::{ApplyStatusEffect, StatusEffectDefinition},
+        Faction,
+    },
+    loading::TextureAssets,
+    physics::{hit_detection::HurtBoxBundle, PhysicsCollisionBundle},
+    session::MatchEntity,
+    GameState,
+};
+
+use super::{
+    economy::{GoldCategory, RejectionReason, Treasury},
+    AllyCastle,
+};
+
+pub struct TowerPlugin;
+
+/// This plugin handles defensive towers that are built around the castles
+/// Tower logic is only active during the State `GameState::Playing`
+impl Plugin for TowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TowerDestroyed>().add_systems(
+            FixedUpdate,
+            (
+                process_build_ally_tower.in_set(SimulationSet::Commands),
+                tower_attack.in_set(SimulationSet::Combat),
+                award_tower_bounty.in_set(SimulationSet::Death),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Offsets of the tower slots relative to the ally castle, mirrored for the enemy castle
+const TOWER_SLOTS: [Vec2; 3] = [
+    Vec2::new(200.0, 200.0),
+    Vec2::new(240.0, 0.0),
+    Vec2::new(200.0, -200.0),
+];
+
+/// The towers built in the slots around a castle
+#[derive(Debug, Default, Component)]
+pub struct TowerSlots {
+    towers: [Option<Entity>; TOWER_SLOTS.len()],
+}
+
+impl TowerSlots {
+    /// Index of the first slot without a living tower
+    pub fn free_slot(&self, towers: &Query<(), With<Tower>>) -> Option<usize> {
+        self.towers
+            .iter()
+            .position(|tower| tower.map_or(true, |entity| !towers.contains(entity)))
+    }
+
+    /// Slot index and entity of every built tower, which might have been destroyed since
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Entity)> + '_ {
+        self.towers
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, tower)| tower.map(|entity| (slot, entity)))
+    }
+
+    pub fn insert(&mut self, slot: usize, entity: Entity) {
+        self.towers[slot] = Some(entity);
+    }
+
+    pub fn count(&self, towers: &Query<(), With<Tower>>) -> usize {
+        self.towers
+            .iter()
+            .flatten()
+            .filter(|entity| towers.contains(**entity))
+            .count()
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Tower {
+    pub damage: Damage,
+    pub fire_rate: Timer,
+    /// Applied to every unit the tower shoots
+    pub status_effect: Option<StatusEffectDefinition>,
+}
+
+impl Tower {
+    pub const COST: usize = 15;
+    pub const HEALTH: f32 = 300.0;
+    /// Gold for destroying an enemy tower
+    const BOUNTY: usize = 3;
+    const DAMAGE: f32 = 15.0;
+    /// Seconds between two shots
+    const FIRE_RATE: f32 = 0.8;
+    const RANGE: f32 = 150.0;
+}
+
+impl Default for Tower {
+    fn default() -> Self {
+        Self {
+            damage: Damage::new(Self::DAMAGE, DamageType::Pierce),
+            fire_rate: Timer::from_seconds(Self::FIRE_RATE, TimerMode::Once),
+            status_effect: None,
+        }
+    }
+}
+
+/// The health of a tower dropped to zero, sent right before it is despawned
+#[derive(Debug, Event)]
+pub struct TowerDestroyed {
+    pub entity: Entity,
+    pub faction: Faction,
+    /// Dealt the final blow, `None` if nobody is credited for it
+    pub killer: Option<HealthDeltaSource>,
+    pub position: Vec2,
+}
+
+#[derive(Debug, Default, Component)]
+pub struct TowerRange;
+
+#[derive(Debug, Default, Bundle)]
+pub struct TowerRangeBundle {
+    pub tower_range: TowerRange,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub collisionlayers: CollisionLayers,
+}
+
+fn process_build_ally_tower(
+    mut commands: Commands,
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut castles: Query<(&GlobalTransform, &mut TowerSlots)>,
+    towers: Query<(), With<Tower>>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+    textures: Res<TextureAssets>,
+) {
+    for ev in playercommand_evr.read() {
+        if ev.action != PlayerAction::BuildTower {
+            continue;
+        }
+
+        if let Some(entity) = ally_castle.0 {
+            if let Ok((transform, mut tower_slots)) = castles.get_mut(entity) {
+                let Some(slot) = tower_slots.free_slot(&towers) else {
+                    treasury.reject(GoldCategory::Towers, RejectionReason::NoSpace);
+                    continue;
+                };
+                if treasury.try_spend(Tower::COST, GoldCategory::Towers) {
+                    tower_slots.towers[slot] = Some(spawn_tower(
+                        &mut commands,
+                        Faction::Ally,
+                        transform.translation().truncate(),
+                        slot,
+                        &textures,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+pub fn spawn_tower(
+    commands: &mut Commands,
+    faction: Faction,
+    castle_position: Vec2,
+    slot: usize,
+    textures: &TextureAssets,
+) -> Entity {
+    let offset = match faction {
+        Faction::Ally => TOWER_SLOTS[slot],
+        Faction::Enemy => TOWER_SLOTS[slot] * Vec2::new(-1.0, 1.0),
+    };
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(64.0, 64.0)),
+                ..Default::default()
+            },
+            texture: textures.castle.clone(),
+            transform: Transform::from_translation((castle_position + offset).extend(1.0)),
+            ..Default::default()
+        })
+        .insert(faction)
+        .insert(MatchEntity)
+        .insert(Tower::default())
+        .insert(Health::new(Tower::HEALTH))
+        .insert(PhysicsCollisionBundle {
+            rigid_body: RigidBody::Static,
+            collider: Collider::ball(32.0),
+            ..Default::default()
+        })
+        .with_children(|children| {
+            children.spawn(HurtBoxBundle {
+                collider: Collider::ball(31.0),
+                collisionlayers: CollisionLayers::new(
+                    [faction.hurt_layer()],
+                    [faction.opposite().hit_layer()],
+                ),
+                ..Default::default()
+            });
+            children.spawn(TowerRangeBundle {
+                collider: Collider::ball(Tower::RANGE),
+                collisionlayers: CollisionLayers::new(
+                    [faction.hit_layer()],
+                    [faction.opposite().hurt_layer()],
+                ),
+                ..Default::default()
+            });
+        })
+        .id()
+}
+
+fn tower_attack(
+    time: Res<Time>,
+    mut towers: Query<(&mut Tower, &GlobalTransform, &Faction)>,
+    tower_ranges: Query<(&ColliderParent, &CollidingEntities), With<TowerRange>>,
+    collider_parents: Query<&ColliderParent>,
+    transforms: Query<&GlobalTransform>,
+    mut dealdamage_evw: EventWriter<DealDamage>,
+    mut applystatuseffect_evw: EventWriter<ApplyStatusEffect>,
+) {
+    for (parent, colliding_entities) in &tower_ranges {
+        if let Ok((mut tower, src_transform, faction)) = towers.get_mut(parent.get()) {
+            if !tower.fire_rate.tick(time.delta()).finished() {
+                continue;
+            }
+
+            let src_point = src_transform.translation().truncate();
+            /* Get the shortest distance Entity */
+            if let Some((target, _)) = colliding_entities
+                .iter()
+                .filter_map(|entity| {
+                    let parent = collider_parents.get(*entity).ok()?.get();
+                    let dst_point = transforms.get(parent).ok()?.translation().truncate();
+
+                    Some((parent, src_point.distance(dst_point)))
+                })
+                .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+            {
+                dealdamage_evw.send(DealDamage {
+                    target,
+                    damage: tower.damage,
+                    source: Some(HealthDeltaSource {
+                        entity: parent.get(),
+                        faction: *faction,
+                    }),
+                });
+                if let Some(effect) = tower.status_effect {
+                    applystatuseffect_evw.send(ApplyStatusEffect {
+                        entity: target,
+                        effect,
+                    });
+                }
+                tower.fire_rate.reset();
+            }
+        }
+    }
+}
+
+fn award_tower_bounty(mut towerdestroyed_evr: EventReader<TowerDestroyed>, mut treasury: Treasury) {
+    for ev in towerdestroyed_evr.read() {
+        if ev.faction == Faction::Enemy {
+            treasury.earn(Tower::BOUNTY, GoldCategory::Bounties, Some(ev.position));
+        }
+    }
+}
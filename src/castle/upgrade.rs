@@ -1,8 +1,13 @@
-use std::time::Duration;
-
 use bevy::prelude::*;
 
-use crate::{castle::SpawnQueue, GameState};
+use crate::{
+    castle::{
+        economy::{GoldCategory, RejectionReason, Treasury},
+        AllyCastle, SpawnQueue,
+    },
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    GameState,
+};
 
 pub struct UpgradePlugin;
 
@@ -11,8 +16,14 @@ pub struct UpgradePlugin;
 impl Plugin for UpgradePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            spawn_cooldown_reduction.run_if(in_state(GameState::Playing)),
+            FixedUpdate,
+            (
+                process_upgrade_spawn_cooldown_reduction,
+                spawn_cooldown_reduction,
+            )
+                .chain()
+                .in_set(SimulationSet::Commands)
+                .run_if(in_state(GameState::Playing)),
         );
     }
 }
@@ -26,6 +37,10 @@ impl SpawnCooldownReduction {
     const BASE_VALUE: f32 = 0.075;
     const MAX_LEVEL: usize = 10;
 
+    pub fn is_max_level(&self) -> bool {
+        self.level >= Self::MAX_LEVEL
+    }
+
     pub fn level_up(&mut self) -> bool {
         if self.level < Self::MAX_LEVEL {
             self.level += 1;
@@ -52,15 +67,36 @@ impl SpawnCooldownReduction {
     }
 }
 
-#[derive(Debug, Default, Component)]
-pub struct SoldierTechtree {}
+fn process_upgrade_spawn_cooldown_reduction(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut spawn_cooldown_reduction: Query<&mut SpawnCooldownReduction>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+) {
+    for ev in playercommand_evr.read() {
+        if ev.action != PlayerAction::UpgradeSpawnCooldownReduction {
+            continue;
+        }
+
+        if let Some(entity) = ally_castle.0 {
+            if let Ok(mut spawn_cooldown_reduction) = spawn_cooldown_reduction.get_mut(entity) {
+                if spawn_cooldown_reduction.is_max_level() {
+                    treasury.reject(GoldCategory::Upgrades, RejectionReason::MaxLevel);
+                } else if treasury
+                    .try_spend(spawn_cooldown_reduction.cost(), GoldCategory::Upgrades)
+                {
+                    spawn_cooldown_reduction.level_up();
+                }
+            }
+        }
+    }
+}
 
 fn spawn_cooldown_reduction(
     mut query: Query<(&SpawnCooldownReduction, &mut SpawnQueue), Changed<SpawnCooldownReduction>>,
 ) {
     for (spawn_cooldown_reduction, mut spawn_queue) in &mut query {
-        spawn_queue.timer.set_duration(Duration::from_secs_f32(
-            2.0 * (1.0 - spawn_cooldown_reduction.get()),
-        ));
+        let interval = spawn_queue.interval;
+        spawn_queue.set_interval(interval, spawn_cooldown_reduction);
     }
 }
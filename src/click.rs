@@ -0,0 +1,263 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::{
+    components::ColliderParent,
+    prelude::{SpatialQuery, SpatialQueryFilter},
+};
+
+use crate::{
+    castle::{
+        economy::{GoldCategory, RejectionReason, Treasury},
+        AllyCastle, Castle,
+    },
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    common::{
+        attributes::HealthDeltaSource,
+        damage::{Damage, DamageType, DealDamage},
+        Faction,
+    },
+    loading::AudioAssets,
+    physics::SensorLayers,
+    session::{MatchEntity, MatchSetupSet},
+    units::UnitKind,
+    GameState,
+};
+
+pub struct ClickPlugin;
+
+/// This plugin handles the player damaging enemy units by clicking on them
+/// Click logic is only active during the State `GameState::Playing`
+impl Plugin for ClickPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClickCooldown>()
+            .add_event::<ClickHit>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_click_cooldown.in_set(MatchSetupSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (advance_click_cooldown, process_click_commands)
+                    .chain()
+                    .in_set(SimulationSet::Commands)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (spawn_click_hit_feedback, animate_click_hit_feedback)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Default, Component)]
+pub struct ClickDamageUpgrade {
+    level: usize,
+}
+
+impl ClickDamageUpgrade {
+    const BASE_VALUE: f32 = 10.0;
+    const MAX_LEVEL: usize = 10;
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn is_max_level(&self) -> bool {
+        self.level >= Self::MAX_LEVEL
+    }
+
+    pub fn level_up(&mut self) -> bool {
+        if self.level < Self::MAX_LEVEL {
+            self.level += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn cost(&self) -> usize {
+        (self.level + 1) * 3
+    }
+
+    pub fn get(&self) -> f32 {
+        Self::BASE_VALUE + (self.level as f32) * 5.0
+    }
+
+    pub fn new(level: usize) -> Self {
+        Self { level }
+    }
+}
+
+/// Time until the player can click damage again
+#[derive(Debug, Resource)]
+pub struct ClickCooldown(Timer);
+
+impl ClickCooldown {
+    /// Seconds between two damaging clicks
+    const SECONDS: f32 = 0.4;
+
+    fn ready() -> Self {
+        let mut timer = Timer::from_seconds(Self::SECONDS, TimerMode::Once);
+        timer.set_elapsed(Duration::from_secs_f32(Self::SECONDS));
+        Self(timer)
+    }
+}
+
+impl Default for ClickCooldown {
+    fn default() -> Self {
+        Self::ready()
+    }
+}
+
+/// An enemy unit was damaged by a click
+#[derive(Debug, Event)]
+pub struct ClickHit {
+    pub position: Vec2,
+    pub damage: f32,
+}
+
+/// Enemy unit whose hurt box contains the point
+pub fn enemy_unit_at(
+    spatial_query: &SpatialQuery,
+    collider_parents: &Query<&ColliderParent>,
+    units: &Query<&GlobalTransform, With<UnitKind>>,
+    point: Vec2,
+) -> Option<Entity> {
+    spatial_query
+        .point_intersections(
+            point,
+            SpatialQueryFilter::new().with_masks([SensorLayers::EnemyHurt]),
+        )
+        .into_iter()
+        .filter_map(|entity| {
+            let parent = collider_parents.get(entity).ok()?.get();
+            let distance = units
+                .get(parent)
+                .ok()?
+                .translation()
+                .truncate()
+                .distance(point);
+            Some((parent, distance))
+        })
+        .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+        .map(|(entity, _)| entity)
+}
+
+fn reset_click_cooldown(mut click_cooldown: ResMut<ClickCooldown>) {
+    *click_cooldown = ClickCooldown::ready();
+}
+
+fn advance_click_cooldown(time: Res<Time>, mut click_cooldown: ResMut<ClickCooldown>) {
+    click_cooldown.0.tick(time.delta());
+}
+
+fn process_click_commands(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut click_cooldown: ResMut<ClickCooldown>,
+    mut click_damages: Query<&mut ClickDamageUpgrade, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+    spatial_query: SpatialQuery,
+    collider_parents: Query<&ColliderParent>,
+    units: Query<&GlobalTransform, With<UnitKind>>,
+    mut dealdamage_evw: EventWriter<DealDamage>,
+    mut clickhit_evw: EventWriter<ClickHit>,
+) {
+    for ev in playercommand_evr.read() {
+        let Some(entity) = ally_castle.0 else {
+            continue;
+        };
+        let Ok(mut click_damage) = click_damages.get_mut(entity) else {
+            continue;
+        };
+
+        match ev.action {
+            PlayerAction::ClickDamage { x, y } => {
+                if !click_cooldown.0.finished() {
+                    continue;
+                }
+
+                let point = Vec2::new(x as f32, y as f32);
+                if let Some(target) =
+                    enemy_unit_at(&spatial_query, &collider_parents, &units, point)
+                {
+                    let damage = click_damage.get();
+                    dealdamage_evw.send(DealDamage {
+                        target,
+                        damage: Damage::new(damage, DamageType::Physical),
+                        source: Some(HealthDeltaSource {
+                            entity,
+                            faction: Faction::Ally,
+                        }),
+                    });
+                    clickhit_evw.send(ClickHit {
+                        position: point,
+                        damage,
+                    });
+                    click_cooldown.0.reset();
+                }
+            }
+            PlayerAction::UpgradeClickDamage => {
+                if click_damage.is_max_level() {
+                    treasury.reject(GoldCategory::Upgrades, RejectionReason::MaxLevel);
+                } else if treasury.try_spend(click_damage.cost(), GoldCategory::Upgrades) {
+                    click_damage.level_up();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Damage number rising from a click hit
+#[derive(Debug, Component)]
+struct ClickHitFeedback(Timer);
+
+fn spawn_click_hit_feedback(
+    mut commands: Commands,
+    mut clickhit_evr: EventReader<ClickHit>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for ev in clickhit_evr.read() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("-{:.0}", ev.damage),
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::rgb(1.0, 0.2, 0.2),
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(ev.position.extend(10.0)),
+                ..Default::default()
+            },
+            AudioBundle {
+                source: audio_assets.hit.clone(),
+                settings: PlaybackSettings::ONCE,
+            },
+            ClickHitFeedback(Timer::from_seconds(0.6, TimerMode::Once)),
+            MatchEntity,
+        ));
+    }
+}
+
+fn animate_click_hit_feedback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut feedbacks: Query<(Entity, &mut ClickHitFeedback, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut feedback, mut transform, mut text) in &mut feedbacks {
+        if feedback.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        transform.translation.y += 40.0 * time.delta_seconds();
+        for section in &mut text.sections {
+            section.style.color.set_a(feedback.0.percent_left());
+        }
+    }
+}
@@ -0,0 +1,185 @@
+pub mod replay;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    session::MatchSetupSet,
+    spell::Spell,
+    units::{order::UnitOrder, UnitKind},
+    GameState,
+};
+
+use self::replay::{Replay, ReplayPlugin};
+
+pub struct PlayerCommandPlugin;
+
+/// This plugin turns player input into tick stamped [`PlayerCommand`]s
+/// Commands are only issued during the State `GameState::Playing`
+impl Plugin for PlayerCommandPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ReplayPlugin)
+            .init_resource::<SimulationTick>()
+            .init_resource::<PendingPlayerActions>()
+            .init_resource::<CommandLog>()
+            .add_event::<PlayerCommand>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    PlayerCommandSet,
+                    SimulationSet::Commands,
+                    SimulationSet::Spawn,
+                    SimulationSet::Combat,
+                    SimulationSet::Damage,
+                    SimulationSet::Health,
+                    SimulationSet::Death,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_commands.in_set(MatchSetupSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                issue_player_commands
+                    .in_set(PlayerCommandSet)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Systems handling [`PlayerCommand`]s run after this set, so a command takes effect in the tick it is stamped with
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct PlayerCommandSet;
+
+/// Stages of a `FixedUpdate` tick, run in this order after the [`PlayerCommandSet`]
+/// Events are only swapped once per frame, so every event is read in the same tick it is sent, by a later stage or an explicitly ordered system
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub enum SimulationSet {
+    /// Player commands, purchases, waves and the enemy commander
+    Commands,
+    /// Queued units leave the castles
+    Spawn,
+    /// Units move, attack and cast spells
+    Combat,
+    /// Damage, status effects and healing turn into changes of health
+    Damage,
+    /// Changes of health are applied and whatever has no health left dies
+    Health,
+    /// Bounties, statistics and the end of the match
+    Death,
+}
+
+/// Everything the player can do during a match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerAction {
+    QueueUnit(UnitKind),
+    /// Removes the unit with this id from the spawn queue and refunds it
+    CancelQueuedUnit(u32),
+    UpgradeSpawnCooldownReduction,
+    UpgradeShield,
+    UpgradeAttackCooldown,
+    BuildTower,
+    /// Allied units gather at this world position, rounded to whole pixels to keep recordings exact
+    /// With `recall` the units already on the battlefield are called back as well
+    SetRallyPoint {
+        x: i32,
+        y: i32,
+        recall: bool,
+    },
+    /// Clears the rally point and sends the gathered units to attack
+    ReleaseRally,
+    /// Selects the allied units inside the area, in whole world pixels
+    SelectUnits {
+        min_x: i32,
+        min_y: i32,
+        max_x: i32,
+        max_y: i32,
+    },
+    /// Gives an order to the selected units
+    OrderUnits(UnitOrder),
+    /// Damages the enemy unit at this world position, in whole pixels
+    ClickDamage {
+        x: i32,
+        y: i32,
+    },
+    UpgradeClickDamage,
+    /// Researches the node with this index in `assets/data/game.tech.ron`
+    Research(usize),
+    /// Builds the economic building with this index in `assets/data/game.economy.ron`
+    Build(usize),
+    /// Casts the spell at this world position, in whole pixels
+    CastSpell {
+        spell: Spell,
+        x: i32,
+        y: i32,
+    },
+}
+
+/// A [`PlayerAction`] together with the simulation tick it was applied in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event, Serialize, Deserialize)]
+pub struct PlayerCommand {
+    pub tick: u64,
+    pub action: PlayerAction,
+}
+
+/// Number of `FixedUpdate` ticks since the match started
+#[derive(Debug, Default, Resource, Deref)]
+pub struct SimulationTick(u64);
+
+/// Actions requested by the ui or keyboard since the last tick
+/// They are stamped and sent as [`PlayerCommand`]s at the start of the next tick
+#[derive(Debug, Default, Resource)]
+pub struct PendingPlayerActions(Vec<PlayerAction>);
+
+impl PendingPlayerActions {
+    pub fn send(&mut self, action: PlayerAction) {
+        self.0.push(action);
+    }
+}
+
+/// Every [`PlayerCommand`] of the current match in order
+#[derive(Debug, Default, Resource, Deref)]
+pub struct CommandLog(Vec<PlayerCommand>);
+
+fn reset_commands(
+    mut tick: ResMut<SimulationTick>,
+    mut pending: ResMut<PendingPlayerActions>,
+    mut command_log: ResMut<CommandLog>,
+) {
+    tick.0 = 0;
+    pending.0.clear();
+    command_log.0.clear();
+}
+
+fn issue_player_commands(
+    mut tick: ResMut<SimulationTick>,
+    mut pending: ResMut<PendingPlayerActions>,
+    mut command_log: ResMut<CommandLog>,
+    mut replay: Option<ResMut<Replay>>,
+    mut playercommand_evw: EventWriter<PlayerCommand>,
+) {
+    tick.0 += 1;
+
+    let commands: Vec<PlayerCommand> = match replay.as_deref_mut() {
+        /* Live input is ignored while a recording is played back */
+        Some(replay) => {
+            pending.0.clear();
+            replay.commands_for(tick.0).collect()
+        }
+        None => pending
+            .0
+            .drain(..)
+            .map(|action| PlayerCommand {
+                tick: tick.0,
+                action,
+            })
+            .collect(),
+    };
+
+    for command in commands {
+        command_log.0.push(command);
+        playercommand_evw.send(command);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    castle::commander::Commander,
+    common::rng::GameRng,
+    session::{MatchSettings, MatchSetupSet},
+    GameState,
+};
+
+use super::{CommandLog, PlayerCommand};
+
+pub struct ReplayPlugin;
+
+/// This plugin handles playing back a [`Recording`] instead of live input
+/// The recording is rewound whenever a match starts
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            rewind_replay.in_set(MatchSetupSet),
+        );
+    }
+}
+
+pub struct RecorderPlugin;
+
+/// This plugin writes a [`Recording`] of every finished match to the `replays` directory
+/// Nothing is written while a replay is running
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            save_recording.run_if(not(resource_exists::<Replay>())),
+        )
+        .add_systems(
+            OnEnter(GameState::Won),
+            save_recording.run_if(not(resource_exists::<Replay>())),
+        );
+    }
+}
+
+/// Seed, settings and player commands of a match, enough to reproduce it exactly
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    /// Recordings from before the difficulty presets were played with the default settings
+    #[serde(default)]
+    pub settings: MatchSettings,
+    pub commands: Vec<PlayerCommand>,
+}
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("Could not access recording: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse recording: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize recording: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let bytes = std::fs::read(path)?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, ron)?;
+        Ok(())
+    }
+}
+
+/// Feeds the commands of a [`Recording`] into the game instead of live input
+#[derive(Debug, Resource)]
+pub struct Replay {
+    recording: Recording,
+    /// Index of the first command that has not been issued yet
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self { recording, next: 0 }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.recording.seed
+    }
+
+    /// Commands stamped with `tick` or earlier that have not been issued yet
+    pub fn commands_for(&mut self, tick: u64) -> impl Iterator<Item = PlayerCommand> + '_ {
+        let start = self.next;
+        let commands = &self.recording.commands;
+        while self.next < commands.len() && commands[self.next].tick <= tick {
+            self.next += 1;
+        }
+        commands[start..self.next].iter().copied()
+    }
+}
+
+fn rewind_replay(replay: Option<ResMut<Replay>>) {
+    if let Some(mut replay) = replay {
+        replay.next = 0;
+    }
+}
+
+fn save_recording(
+    rng: Res<GameRng>,
+    settings: Res<MatchSettings>,
+    commander: Res<Commander>,
+    command_log: Res<CommandLog>,
+) {
+    /* The strategy is fixed, so playing back does not depend on how the generator is drawn from during setup */
+    let recording = Recording {
+        seed: rng.seed(),
+        settings: MatchSettings {
+            enemy_strategy: Some(commander.strategy),
+            ..*settings
+        },
+        commands: command_log.to_vec(),
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = format!("replays/{}.replay.ron", timestamp);
+
+    match recording.save(&path) {
+        Ok(()) => info!("Saved replay to {}", path),
+        Err(error) => warn!("Could not save replay to {}: {}", path, error),
+    }
+}
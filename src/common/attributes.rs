@@ -1,7 +1,13 @@
 use bevy::prelude::*;
-use std::{fmt::Display, ops::Add};
+use std::{collections::HashMap, fmt::Display, ops::Add};
 
-use crate::{castle::Gold, common::Faction, GameState};
+use crate::{
+    castle::tower::{Tower, TowerDestroyed},
+    command::SimulationSet,
+    common::Faction,
+    units::{death::UnitDied, UnitKind},
+    GameState,
+};
 
 pub struct AttributesPlugin;
 
@@ -9,10 +15,15 @@ pub struct AttributesPlugin;
 /// Attribure logic is only active during the State `GameState::Playing`
 impl Plugin for AttributesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ApplyHealthDelta>().add_systems(
-            Update,
-            (apply_health_delta, die).run_if(in_state(GameState::Playing)),
-        );
+        app.add_event::<ApplyHealthDelta>()
+            .init_resource::<LastHits>()
+            .add_systems(
+                FixedUpdate,
+                (apply_health_delta, die)
+                    .chain()
+                    .in_set(SimulationSet::Health)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -38,38 +49,89 @@ impl Display for Health {
     }
 }
 
+/// Who caused a change of health
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthDeltaSource {
+    /// Unit, tower or castle, which might have been despawned since
+    pub entity: Entity,
+    pub faction: Faction,
+}
+
 #[derive(Debug, Event)]
 pub struct ApplyHealthDelta {
     pub entity: Entity,
     pub delta: f32,
+    /// `None` for status effects and other changes nobody is credited for
+    pub source: Option<HealthDeltaSource>,
 }
 
-fn apply_health_delta(
+/// Source of the last damage every entity took this tick, credited if the entity dies
+#[derive(Debug, Default, Resource)]
+struct LastHits(HashMap<Entity, Option<HealthDeltaSource>>);
+
+pub fn apply_health_delta(
     mut applyhealthdelta_evr: EventReader<ApplyHealthDelta>,
     mut health_query: Query<&mut Health>,
+    mut last_hits: ResMut<LastHits>,
 ) {
     for ev in applyhealthdelta_evr.read() {
-        println!("{:?}", ev);
+        debug!("{:?}", ev);
         if let Ok(mut health) = health_query.get_mut(ev.entity) {
             health.apply(ev.delta);
+            if ev.delta < 0.0 {
+                last_hits.0.insert(ev.entity, ev.source);
+            }
         }
     }
 }
 
-fn die(
+/// Despawns everything without health left, what happens on the death of a unit or tower is driven by [`UnitDied`] and [`TowerDestroyed`]
+/// Runs after all changes of health of the tick, so a unit healed back up in the same tick does not die
+pub fn die(
     mut commands: Commands,
-    health_query: Query<(Entity, &Health, &Faction), (Changed<Health>, Without<Immortal>)>,
-    mut gold: ResMut<Gold>,
+    health_query: Query<
+        (
+            Entity,
+            &Health,
+            Option<(&Faction, &GlobalTransform)>,
+            Option<&UnitKind>,
+            Has<Tower>,
+        ),
+        (Changed<Health>, Without<Immortal>),
+    >,
+    mut last_hits: ResMut<LastHits>,
+    mut unitdied_evw: EventWriter<UnitDied>,
+    mut towerdestroyed_evw: EventWriter<TowerDestroyed>,
 ) {
-    for (entity, health, faction) in &health_query {
-        if health.current == 0.0 {
-            commands.entity(entity).despawn_recursive();
+    for (entity, health, faction, kind, tower) in &health_query {
+        if health.current > 0.0 {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
 
-            if let Faction::Enemy = faction {
-                gold.0 += 3;
-            }
+        let Some((faction, transform)) = faction else {
+            continue;
+        };
+        let killer = last_hits.0.get(&entity).copied().flatten();
+        let position = transform.translation().truncate();
+        if let Some(kind) = kind {
+            unitdied_evw.send(UnitDied {
+                entity,
+                faction: *faction,
+                kind: *kind,
+                killer,
+                position,
+            });
+        } else if tower {
+            towerdestroyed_evw.send(TowerDestroyed {
+                entity,
+                faction: *faction,
+                killer,
+                position,
+            });
         }
     }
+    last_hits.0.clear();
 }
 
 #[derive(Debug, Default, Component)]
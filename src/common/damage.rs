@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    castle::{tower::Tower, Castle},
+    command::SimulationSet,
+    GameState,
+};
+
+use super::{
+    attributes::{ApplyHealthDelta, HealthDeltaSource},
+    rng::GameRng,
+    stats::{Stat, Stats},
+};
+
+pub struct DamagePlugin;
+
+/// This plugin resolves damage against the armor and resistances of its target before it is applied to the health
+/// Damage logic is only active during the State `GameState::Playing`
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DealDamage>()
+            .add_event::<DamageDealt>()
+            .add_systems(
+                FixedUpdate,
+                resolve_damage
+                    .in_set(SimulationSet::Damage)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Critical hits deal this many times the damage
+pub const CRITICAL_MULTIPLIER: f32 = 2.0;
+
+/// Castles and towers take this many times the damage of [`DamageType::Siege`]
+const SIEGE_STRUCTURE_MULTIPLIER: f32 = 2.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageType {
+    /// Reduced by armor
+    #[default]
+    Physical,
+    /// Reduced by half the armor
+    Pierce,
+    /// Ignores armor, reduced by magic resistance
+    Magic,
+    /// Reduced by armor, deals more damage to castles and towers
+    Siege,
+}
+
+impl DamageType {
+    /// Resistance of the target against this type, 100 halves the damage
+    fn resistance(&self, stats: &Stats) -> f32 {
+        match self {
+            DamageType::Physical | DamageType::Siege => stats.get(Stat::Armor),
+            DamageType::Pierce => stats.get(Stat::Armor) / 2.0,
+            DamageType::Magic => stats.get(Stat::MagicResist),
+        }
+    }
+}
+
+/// Damage as hitboxes, towers, spells and clicks deal it, before it is resolved against the target
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Damage {
+    pub amount: f32,
+    pub damage_type: DamageType,
+    /// Chance from 0 to 1 to deal [`CRITICAL_MULTIPLIER`] times the damage
+    pub crit_chance: f32,
+}
+
+impl Damage {
+    pub fn new(amount: f32, damage_type: DamageType) -> Self {
+        Self {
+            amount,
+            damage_type,
+            crit_chance: 0.0,
+        }
+    }
+
+    /// Damage of a single attack of a unit with these [`Stats`]
+    pub fn from_stats(stats: &Stats, damage_type: DamageType) -> Self {
+        Self {
+            amount: stats.get(Stat::Damage),
+            damage_type,
+            crit_chance: stats.get(Stat::CritChance),
+        }
+    }
+
+    /// Damage taken and damage mitigated by a target with these stats, `structure` for castles and towers
+    pub fn resolve(&self, stats: Option<&Stats>, structure: bool, critical: bool) -> (f32, f32) {
+        let mut amount = self.amount;
+        if critical {
+            amount *= CRITICAL_MULTIPLIER;
+        }
+        if self.damage_type == DamageType::Siege && structure {
+            amount *= SIEGE_STRUCTURE_MULTIPLIER;
+        }
+
+        /* Castles and towers have no stats and take the full damage */
+        let resistance = stats.map_or(0.0, |stats| self.damage_type.resistance(stats).max(0.0));
+        let resolved = amount * 100.0 / (100.0 + resistance);
+        (resolved, amount - resolved)
+    }
+}
+
+/// Deals `damage` to `target` after resolving it against its armor and resistances
+#[derive(Debug, Event)]
+pub struct DealDamage {
+    pub target: Entity,
+    pub damage: Damage,
+    pub source: Option<HealthDeltaSource>,
+}
+
+/// Breakdown of a resolved [`DealDamage`]
+#[derive(Debug, Event)]
+pub struct DamageDealt {
+    pub target: Entity,
+    pub source: Option<HealthDeltaSource>,
+    pub damage_type: DamageType,
+    /// Damage before critical hits and resistances
+    pub base: f32,
+    pub critical: bool,
+    /// Damage absorbed by the resistances of the target
+    pub mitigated: f32,
+    /// Damage taken from the health of the target
+    pub amount: f32,
+}
+
+pub fn resolve_damage(
+    mut dealdamage_evr: EventReader<DealDamage>,
+    targets: Query<Option<&Stats>>,
+    structures: Query<(), Or<(With<Castle>, With<Tower>)>>,
+    mut rng: ResMut<GameRng>,
+    mut applyhealthdelta_evw: EventWriter<ApplyHealthDelta>,
+    mut damagedealt_evw: EventWriter<DamageDealt>,
+) {
+    for ev in dealdamage_evr.read() {
+        /* The target may have died earlier this frame */
+        let Ok(stats) = targets.get(ev.target) else {
+            continue;
+        };
+        let damage = ev.damage;
+
+        /* Only roll if a crit is possible, so the random sequence of everything else stays the same */
+        let critical = damage.crit_chance > 0.0 && rng.gen_bool(damage.crit_chance.min(1.0) as f64);
+        let (resolved, mitigated) = damage.resolve(stats, structures.contains(ev.target), critical);
+
+        applyhealthdelta_evw.send(ApplyHealthDelta {
+            entity: ev.target,
+            delta: -resolved,
+            source: ev.source,
+        });
+        damagedealt_evw.send(DamageDealt {
+            target: ev.target,
+            source: ev.source,
+            damage_type: damage.damage_type,
+            base: damage.amount,
+            critical,
+            mitigated,
+            amount: resolved,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 50 armor and 100 magic resistance
+    fn stats() -> Stats {
+        Stats::new([(Stat::Armor, 50.0), (Stat::MagicResist, 100.0)])
+    }
+
+    #[test]
+    fn physical_is_reduced_by_armor() {
+        let damage = Damage::new(30.0, DamageType::Physical);
+        assert_eq!(damage.resolve(Some(&stats()), false, false), (20.0, 10.0));
+    }
+
+    #[test]
+    fn pierce_is_reduced_by_half_the_armor() {
+        let damage = Damage::new(30.0, DamageType::Pierce);
+        assert_eq!(damage.resolve(Some(&stats()), false, false), (24.0, 6.0));
+    }
+
+    #[test]
+    fn magic_ignores_armor() {
+        let damage = Damage::new(30.0, DamageType::Magic);
+        assert_eq!(damage.resolve(Some(&stats()), false, false), (15.0, 15.0));
+    }
+
+    #[test]
+    fn siege_deals_more_damage_to_structures_only() {
+        let damage = Damage::new(30.0, DamageType::Siege);
+        assert_eq!(damage.resolve(Some(&stats()), false, false), (20.0, 10.0));
+        assert_eq!(damage.resolve(None, true, false), (60.0, 0.0));
+
+        let damage = Damage::new(30.0, DamageType::Physical);
+        assert_eq!(damage.resolve(None, true, false), (30.0, 0.0));
+    }
+
+    #[test]
+    fn targets_without_stats_take_the_full_damage() {
+        for damage_type in [
+            DamageType::Physical,
+            DamageType::Pierce,
+            DamageType::Magic,
+            DamageType::Siege,
+        ] {
+            let damage = Damage::new(30.0, damage_type);
+            assert_eq!(damage.resolve(None, false, false), (30.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn negative_armor_does_not_increase_the_damage() {
+        let stats = Stats::new([(Stat::Armor, -50.0)]);
+        let damage = Damage::new(30.0, DamageType::Physical);
+        assert_eq!(damage.resolve(Some(&stats), false, false), (30.0, 0.0));
+    }
+
+    #[test]
+    fn critical_hits_are_multiplied_before_resistances() {
+        let damage = Damage::new(30.0, DamageType::Physical);
+        assert_eq!(damage.resolve(Some(&stats()), false, true), (40.0, 20.0));
+
+        let damage = Damage::new(30.0, DamageType::Siege);
+        assert_eq!(damage.resolve(None, true, true), (120.0, 0.0));
+    }
+}
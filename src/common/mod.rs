@@ -1,22 +1,41 @@
 pub mod attributes;
+pub mod damage;
+pub mod rng;
+pub mod stats;
+pub mod status;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::physics::SensorLayers;
 
-use self::attributes::AttributesPlugin;
+use self::{
+    attributes::AttributesPlugin, damage::DamagePlugin, rng::RngPlugin, status::StatusPlugin,
+};
 
 pub struct CommonPlugin;
 
-/// This plugin handles attributes related stuff like health
+/// This plugin handles attributes related stuff like health, damage and status effects
 /// Attribure logic is only active during the State `GameState::Playing`
 impl Plugin for CommonPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(AttributesPlugin);
+        app.add_plugins((AttributesPlugin, DamagePlugin, RngPlugin, StatusPlugin));
     }
 }
 
-#[derive(Debug, Component, Clone, Copy)]
+/// World position under the cursor, `None` if the cursor is outside of the window
+pub fn viewport_to_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| ray.origin.truncate())
+}
+
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Faction {
     Ally,
     Enemy,
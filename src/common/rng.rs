@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{session::MatchSetupSet, GameState};
+
+pub struct RngPlugin;
+
+/// This plugin handles the random number generator used by the game simulation
+/// The generator is reseeded whenever a match starts, so a seed fully determines a match
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchSeed>()
+            .insert_resource(GameRng::new(0))
+            .add_systems(OnEnter(GameState::Playing), seed_rng.in_set(MatchSetupSet));
+    }
+}
+
+/// Seed for the next match, a random one is picked per match if not set
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct MatchSeed(pub Option<u64>);
+
+/// The only source of randomness for gameplay systems
+/// Only use it from systems in `FixedUpdate`, so the sequence of numbers stays reproducible
+#[derive(Debug, Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seed the current match was started with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Reseeds in place, so setup systems ordered after this one already draw from the new seed
+pub fn seed_rng(mut rng: ResMut<GameRng>, match_seed: Res<MatchSeed>) {
+    let seed = match_seed.0.unwrap_or_else(rand::random);
+    info!("Match seed: {}", seed);
+    *rng = GameRng::new(seed);
+}
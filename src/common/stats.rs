@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::status::StatusKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    MaxHealth,
+    /// Damage of a single attack
+    Damage,
+    /// Seconds between two attacks
+    AttackCooldown,
+    /// Pixels per second
+    MoveSpeed,
+    /// Radius of the EnemyFinder
+    DetectionRadius,
+    /// Reduces incoming physical, pierce and siege damage, 100 armor halves it
+    Armor,
+    /// Reduces incoming magic damage, 100 magic resistance halves it
+    MagicResist,
+    /// Chance from 0 to 1 that an attack is a critical hit
+    CritChance,
+}
+
+/// Where a [`StatModifier`] comes from, a source has at most one modifier per [`Stat`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ModifierSource {
+    ShieldUpgrade,
+    AttackCooldownUpgrade,
+    /// Index of the researched node of the tech tree
+    Tech(usize),
+    Status(StatusKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ModifierValue {
+    /// Added to the base value
+    Add(f32),
+    /// Multiplied with the base value after all additions
+    Multiply(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatModifier {
+    pub source: ModifierSource,
+    pub stat: Stat,
+    pub value: ModifierValue,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StatModifiers(Vec<StatModifier>);
+
+impl StatModifiers {
+    pub fn get(&self, source: ModifierSource, stat: Stat) -> Option<&StatModifier> {
+        self.0
+            .iter()
+            .find(|modifier| modifier.source == source && modifier.stat == stat)
+    }
+
+    /// Adds the modifier, replacing the one of the same source and stat
+    pub fn set(&mut self, modifier: StatModifier) {
+        match self
+            .0
+            .iter_mut()
+            .find(|other| other.source == modifier.source && other.stat == modifier.stat)
+        {
+            Some(other) => *other = modifier,
+            None => self.0.push(modifier),
+        }
+    }
+
+    pub fn remove(&mut self, source: ModifierSource, stat: Stat) {
+        self.0
+            .retain(|modifier| modifier.source != source || modifier.stat != stat);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatModifier> {
+        self.0.iter()
+    }
+}
+
+/// Base values of an entity and the modifiers upgrades, buffs and status effects push into them
+#[derive(Debug, Clone, Component)]
+pub struct Stats {
+    base: HashMap<Stat, f32>,
+    pub modifiers: StatModifiers,
+}
+
+impl Stats {
+    pub fn new(base: impl IntoIterator<Item = (Stat, f32)>) -> Self {
+        Self {
+            base: base.into_iter().collect(),
+            modifiers: StatModifiers::default(),
+        }
+    }
+
+    pub fn with_modifiers<'a>(
+        mut self,
+        modifiers: impl IntoIterator<Item = &'a StatModifier>,
+    ) -> Self {
+        for modifier in modifiers {
+            self.modifiers.set(*modifier);
+        }
+        self
+    }
+
+    pub fn base(&self, stat: Stat) -> f32 {
+        self.base.get(&stat).copied().unwrap_or_default()
+    }
+
+    pub fn get(&self, stat: Stat) -> f32 {
+        let (add, multiply) = self
+            .modifiers
+            .iter()
+            .filter(|modifier| modifier.stat == stat)
+            .fold((0.0, 1.0), |(add, multiply), modifier| {
+                match modifier.value {
+                    ModifierValue::Add(value) => (add + value, multiply),
+                    ModifierValue::Multiply(value) => (add, multiply * value),
+                }
+            });
+        (self.base(stat) + add) * multiply
+    }
+
+    /// Modified value relative to the base value, 1 without modifiers
+    pub fn ratio(&self, stat: Stat) -> f32 {
+        let base = self.base(stat);
+        if base == 0.0 {
+            1.0
+        } else {
+            self.get(stat) / base
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modifier(source: ModifierSource, stat: Stat, value: ModifierValue) -> StatModifier {
+        StatModifier {
+            source,
+            stat,
+            value,
+        }
+    }
+
+    #[test]
+    fn additions_are_applied_before_multiplications() {
+        let multiply = modifier(
+            ModifierSource::AttackCooldownUpgrade,
+            Stat::Damage,
+            ModifierValue::Multiply(2.0),
+        );
+        let add = modifier(
+            ModifierSource::ShieldUpgrade,
+            Stat::Damage,
+            ModifierValue::Add(5.0),
+        );
+
+        let stats = Stats::new([(Stat::Damage, 10.0)]).with_modifiers([multiply, add].iter());
+        assert_eq!(stats.get(Stat::Damage), 30.0);
+
+        let stats = Stats::new([(Stat::Damage, 10.0)]).with_modifiers([add, multiply].iter());
+        assert_eq!(stats.get(Stat::Damage), 30.0);
+        assert_eq!(stats.ratio(Stat::Damage), 3.0);
+    }
+
+    #[test]
+    fn modifiers_of_different_sources_stack() {
+        let stats = Stats::new([(Stat::MoveSpeed, 100.0), (Stat::Armor, 10.0)]).with_modifiers(
+            [
+                modifier(
+                    ModifierSource::Status(StatusKind::Slow),
+                    Stat::MoveSpeed,
+                    ModifierValue::Multiply(0.5),
+                ),
+                modifier(
+                    ModifierSource::Tech(0),
+                    Stat::MoveSpeed,
+                    ModifierValue::Multiply(1.5),
+                ),
+                modifier(
+                    ModifierSource::Tech(1),
+                    Stat::Armor,
+                    ModifierValue::Add(5.0),
+                ),
+                modifier(
+                    ModifierSource::ShieldUpgrade,
+                    Stat::Armor,
+                    ModifierValue::Add(20.0),
+                ),
+            ]
+            .iter(),
+        );
+
+        assert_eq!(stats.get(Stat::MoveSpeed), 75.0);
+        assert_eq!(stats.get(Stat::Armor), 35.0);
+        assert_eq!(stats.base(Stat::Armor), 10.0);
+    }
+
+    #[test]
+    fn a_source_has_one_modifier_per_stat() {
+        let mut stats = Stats::new([(Stat::Armor, 0.0), (Stat::MaxHealth, 100.0)]);
+        stats.modifiers.set(modifier(
+            ModifierSource::ShieldUpgrade,
+            Stat::Armor,
+            ModifierValue::Add(10.0),
+        ));
+        stats.modifiers.set(modifier(
+            ModifierSource::ShieldUpgrade,
+            Stat::MaxHealth,
+            ModifierValue::Add(50.0),
+        ));
+        stats.modifiers.set(modifier(
+            ModifierSource::ShieldUpgrade,
+            Stat::Armor,
+            ModifierValue::Add(20.0),
+        ));
+
+        assert_eq!(stats.modifiers.iter().count(), 2);
+        assert_eq!(stats.get(Stat::Armor), 20.0);
+        assert_eq!(stats.get(Stat::MaxHealth), 150.0);
+    }
+
+    #[test]
+    fn removing_a_modifier_keeps_the_others() {
+        let mut stats = Stats::new([(Stat::MoveSpeed, 100.0)]).with_modifiers(
+            [
+                modifier(
+                    ModifierSource::Status(StatusKind::Slow),
+                    Stat::MoveSpeed,
+                    ModifierValue::Multiply(0.5),
+                ),
+                modifier(
+                    ModifierSource::Status(StatusKind::Stun),
+                    Stat::MoveSpeed,
+                    ModifierValue::Multiply(0.0),
+                ),
+            ]
+            .iter(),
+        );
+        assert_eq!(stats.get(Stat::MoveSpeed), 0.0);
+
+        stats
+            .modifiers
+            .remove(ModifierSource::Status(StatusKind::Stun), Stat::MoveSpeed);
+        assert_eq!(stats.get(Stat::MoveSpeed), 50.0);
+        assert_eq!(
+            stats
+                .modifiers
+                .get(ModifierSource::Status(StatusKind::Stun), Stat::MoveSpeed),
+            None
+        );
+    }
+
+    #[test]
+    fn stats_without_a_base_value() {
+        let stats = Stats::new([]).with_modifiers(
+            [modifier(
+                ModifierSource::Tech(0),
+                Stat::CritChance,
+                ModifierValue::Add(0.1),
+            )]
+            .iter(),
+        );
+
+        assert_eq!(stats.get(Stat::CritChance), 0.1);
+        assert_eq!(stats.ratio(Stat::CritChance), 1.0);
+    }
+}
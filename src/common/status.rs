@@ -0,0 +1,443 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{command::SimulationSet, GameState};
+
+use super::{
+    attributes::ApplyHealthDelta,
+    damage::resolve_damage,
+    stats::{ModifierSource, ModifierValue, Stat, StatModifier, Stats},
+};
+
+pub struct StatusPlugin;
+
+/// This plugin handles timed status effects like poison, slow, stun and regeneration
+/// Status logic is only active during the State `GameState::Playing`
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatusEffect>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_status_effects,
+                    tick_status_effects,
+                    apply_status_modifiers,
+                )
+                    .chain()
+                    /* Both change health, so the order has to be the same in every replay */
+                    .after(resolve_damage)
+                    .in_set(SimulationSet::Damage)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                update_stun_overlays.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Loses `magnitude` health per second and stack
+    Poison,
+    /// Moves `magnitude` (0 to 1) slower
+    Slow,
+    /// Does not move and its hitboxes do not hit
+    Stun,
+    /// Gains `magnitude` health per second
+    Regeneration,
+}
+
+/// What happens when an effect is applied to an entity that already has one of the same kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusStacking {
+    /// The new effect replaces the old one and restarts the duration
+    Refresh,
+    /// Every application adds a stack up to `max` and restarts the duration
+    Stack { max: u32 },
+    /// The higher magnitude is kept, on a tie the longer duration
+    StrongestWins,
+}
+
+impl StatusKind {
+    pub fn stacking(&self) -> StatusStacking {
+        match self {
+            StatusKind::Poison => StatusStacking::Stack { max: 5 },
+            StatusKind::Slow | StatusKind::Stun => StatusStacking::StrongestWins,
+            StatusKind::Regeneration => StatusStacking::Refresh,
+        }
+    }
+}
+
+/// A status effect as it is applied by hitboxes, spells and towers
+/// Can be part of `assets/data/game.units.ron`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffectDefinition {
+    pub kind: StatusKind,
+    pub magnitude: f32,
+    /// Seconds
+    pub duration: f32,
+}
+
+/// A status effect that is currently running on an entity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub magnitude: f32,
+    /// Seconds until the effect ends
+    pub remaining: f32,
+    pub stacks: u32,
+}
+
+impl From<StatusEffectDefinition> for StatusEffect {
+    fn from(definition: StatusEffectDefinition) -> Self {
+        Self {
+            kind: definition.kind,
+            magnitude: definition.magnitude,
+            remaining: definition.duration,
+            stacks: 1,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Component)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn apply(&mut self, definition: StatusEffectDefinition) {
+        let Some(effect) = self
+            .0
+            .iter_mut()
+            .find(|effect| effect.kind == definition.kind)
+        else {
+            self.0.push(definition.into());
+            return;
+        };
+
+        match definition.kind.stacking() {
+            StatusStacking::Refresh => *effect = definition.into(),
+            StatusStacking::Stack { max } => {
+                effect.stacks = (effect.stacks + 1).min(max);
+                effect.magnitude = effect.magnitude.max(definition.magnitude);
+                effect.remaining = definition.duration;
+            }
+            StatusStacking::StrongestWins => {
+                if definition.magnitude > effect.magnitude {
+                    *effect = definition.into();
+                } else if definition.magnitude == effect.magnitude {
+                    effect.remaining = effect.remaining.max(definition.duration);
+                }
+            }
+        }
+    }
+
+    /// Counts down the running effects and drops the ones that ran out
+    fn tick(&mut self, seconds: f32) {
+        for effect in &mut self.0 {
+            effect.remaining -= seconds;
+        }
+        self.0.retain(|effect| effect.remaining > 0.0);
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.0.iter().any(|effect| effect.kind == StatusKind::Stun)
+    }
+
+    /// Move speed modifier of the strongest effect of the kind, `None` if the kind does not change the stats
+    fn modifier(&self, kind: StatusKind) -> Option<StatModifier> {
+        let factor = match kind {
+            StatusKind::Slow => {
+                1.0 - self
+                    .0
+                    .iter()
+                    .filter(|effect| effect.kind == StatusKind::Slow)
+                    .map(|effect| effect.magnitude.clamp(0.0, 1.0))
+                    .reduce(f32::max)?
+            }
+            StatusKind::Stun if self.is_stunned() => 0.0,
+            _ => return None,
+        };
+
+        Some(StatModifier {
+            source: ModifierSource::Status(kind),
+            stat: Stat::MoveSpeed,
+            value: ModifierValue::Multiply(factor),
+        })
+    }
+
+    /// Health per second gained (positive) or lost (negative) through the running effects
+    fn health_per_second(&self) -> f32 {
+        self.0
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::Poison => -effect.magnitude * effect.stacks as f32,
+                StatusKind::Regeneration => effect.magnitude * effect.stacks as f32,
+                StatusKind::Slow | StatusKind::Stun => 0.0,
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Event)]
+pub struct ApplyStatusEffect {
+    pub entity: Entity,
+    pub effect: StatusEffectDefinition,
+}
+
+fn apply_status_effects(
+    mut commands: Commands,
+    mut applystatuseffect_evr: EventReader<ApplyStatusEffect>,
+    mut status_effects: Query<&mut StatusEffects>,
+) {
+    /* Entities hit for the first time get the component at the end of the frame, collect all their effects until then */
+    let mut added: Vec<(Entity, StatusEffects)> = vec![];
+
+    for ev in applystatuseffect_evr.read() {
+        if let Ok(mut status_effects) = status_effects.get_mut(ev.entity) {
+            status_effects.apply(ev.effect);
+        } else if let Some((_, status_effects)) =
+            added.iter_mut().find(|(entity, _)| *entity == ev.entity)
+        {
+            status_effects.apply(ev.effect);
+        } else {
+            added.push((ev.entity, StatusEffects(vec![ev.effect.into()])));
+        }
+    }
+
+    for (entity, status_effects) in added {
+        /* The target may have died this frame */
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.insert(status_effects);
+        }
+    }
+}
+
+fn tick_status_effects(
+    time: Res<Time>,
+    mut status_effects: Query<(Entity, &mut StatusEffects)>,
+    mut applyhealthdelta_evw: EventWriter<ApplyHealthDelta>,
+) {
+    for (entity, mut status_effects) in &mut status_effects {
+        if status_effects.0.is_empty() {
+            continue;
+        }
+
+        let delta = status_effects.health_per_second() * time.delta_seconds();
+        if delta != 0.0 {
+            applyhealthdelta_evw.send(ApplyHealthDelta {
+                entity,
+                delta,
+                source: None,
+            });
+        }
+
+        status_effects.tick(time.delta_seconds());
+    }
+}
+
+fn apply_status_modifiers(
+    mut status_effects: Query<(&StatusEffects, &mut Stats), Changed<StatusEffects>>,
+) {
+    for (status_effects, mut stats) in &mut status_effects {
+        for kind in [StatusKind::Slow, StatusKind::Stun] {
+            let source = ModifierSource::Status(kind);
+            let modifier = status_effects.modifier(kind);
+            /* Only touch the stats if something changed, they are applied on change */
+            if stats.modifiers.get(source, Stat::MoveSpeed) == modifier.as_ref() {
+                continue;
+            }
+
+            match modifier {
+                Some(modifier) => stats.modifiers.set(modifier),
+                None => stats.modifiers.remove(source, Stat::MoveSpeed),
+            }
+        }
+    }
+}
+
+/// Tint drawn over stunned units
+#[derive(Debug, Component)]
+struct StunOverlay;
+
+fn update_stun_overlays(
+    mut commands: Commands,
+    stunnables: Query<(Entity, &StatusEffects, &Sprite, Option<&Children>), Changed<StatusEffects>>,
+    overlays: Query<(), With<StunOverlay>>,
+) {
+    for (entity, status_effects, sprite, children) in &stunnables {
+        let overlay = children
+            .into_iter()
+            .flat_map(|children| children.iter().copied())
+            .find(|child| overlays.contains(*child));
+
+        match (status_effects.is_stunned(), overlay) {
+            (true, None) => {
+                commands.entity(entity).with_children(|children| {
+                    children.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgba(0.5, 0.8, 1.0, 0.6),
+                                custom_size: sprite.custom_size,
+                                ..Default::default()
+                            },
+                            transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                            ..Default::default()
+                        },
+                        StunOverlay,
+                    ));
+                });
+            }
+            (false, Some(overlay)) => commands.entity(overlay).despawn_recursive(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(kind: StatusKind, magnitude: f32, duration: f32) -> StatusEffectDefinition {
+        StatusEffectDefinition {
+            kind,
+            magnitude,
+            duration,
+        }
+    }
+
+    #[test]
+    fn first_application_adds_a_single_stack() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Poison, 2.0, 3.0));
+
+        assert_eq!(
+            status_effects.0,
+            vec![StatusEffect {
+                kind: StatusKind::Poison,
+                magnitude: 2.0,
+                remaining: 3.0,
+                stacks: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn refresh_replaces_the_running_effect() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Regeneration, 4.0, 5.0));
+        status_effects.tick(3.0);
+        status_effects.apply(definition(StatusKind::Regeneration, 1.0, 4.0));
+
+        assert_eq!(status_effects.0.len(), 1);
+        assert_eq!(status_effects.0[0].magnitude, 1.0);
+        assert_eq!(status_effects.0[0].remaining, 4.0);
+        assert_eq!(status_effects.0[0].stacks, 1);
+    }
+
+    #[test]
+    fn stack_adds_up_to_the_maximum_and_restarts_the_duration() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Poison, 2.0, 3.0));
+        status_effects.tick(1.0);
+        status_effects.apply(definition(StatusKind::Poison, 1.0, 3.0));
+
+        assert_eq!(status_effects.0.len(), 1);
+        assert_eq!(status_effects.0[0].stacks, 2);
+        assert_eq!(status_effects.0[0].magnitude, 2.0);
+        assert_eq!(status_effects.0[0].remaining, 3.0);
+
+        for _ in 0..10 {
+            status_effects.apply(definition(StatusKind::Poison, 2.0, 3.0));
+        }
+        assert_eq!(status_effects.0[0].stacks, 5);
+        assert_eq!(status_effects.health_per_second(), -10.0);
+    }
+
+    #[test]
+    fn strongest_wins_keeps_the_higher_magnitude() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Slow, 0.3, 2.0));
+        status_effects.apply(definition(StatusKind::Slow, 0.5, 1.0));
+        assert_eq!(status_effects.0[0].magnitude, 0.5);
+        assert_eq!(status_effects.0[0].remaining, 1.0);
+
+        status_effects.apply(definition(StatusKind::Slow, 0.2, 10.0));
+        assert_eq!(status_effects.0.len(), 1);
+        assert_eq!(status_effects.0[0].magnitude, 0.5);
+        assert_eq!(status_effects.0[0].remaining, 1.0);
+    }
+
+    #[test]
+    fn strongest_wins_keeps_the_longer_duration_on_a_tie() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Slow, 0.5, 1.0));
+        status_effects.apply(definition(StatusKind::Slow, 0.5, 4.0));
+        assert_eq!(status_effects.0[0].remaining, 4.0);
+
+        status_effects.apply(definition(StatusKind::Slow, 0.5, 2.0));
+        assert_eq!(status_effects.0[0].remaining, 4.0);
+    }
+
+    #[test]
+    fn different_kinds_run_side_by_side() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Poison, 2.0, 3.0));
+        status_effects.apply(definition(StatusKind::Regeneration, 5.0, 3.0));
+
+        assert_eq!(status_effects.0.len(), 2);
+        assert_eq!(status_effects.health_per_second(), 3.0);
+    }
+
+    #[test]
+    fn tick_drops_effects_that_ran_out() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Poison, 1.0, 1.0));
+        status_effects.apply(definition(StatusKind::Slow, 0.5, 3.0));
+
+        status_effects.tick(0.5);
+        assert_eq!(status_effects.0.len(), 2);
+
+        status_effects.tick(0.5);
+        assert_eq!(status_effects.0.len(), 1);
+        assert_eq!(status_effects.0[0].kind, StatusKind::Slow);
+        assert_eq!(status_effects.0[0].remaining, 2.0);
+
+        status_effects.tick(2.0);
+        assert!(status_effects.0.is_empty());
+    }
+
+    #[test]
+    fn stun_stops_movement_until_it_expires() {
+        let mut status_effects = StatusEffects::default();
+        assert!(!status_effects.is_stunned());
+        assert_eq!(status_effects.modifier(StatusKind::Stun), None);
+
+        status_effects.apply(definition(StatusKind::Stun, 1.0, 1.0));
+        assert!(status_effects.is_stunned());
+        assert_eq!(
+            status_effects
+                .modifier(StatusKind::Stun)
+                .map(|modifier| modifier.value),
+            Some(ModifierValue::Multiply(0.0))
+        );
+
+        status_effects.tick(1.0);
+        assert!(!status_effects.is_stunned());
+        assert_eq!(status_effects.modifier(StatusKind::Stun), None);
+    }
+
+    #[test]
+    fn slow_modifier_uses_the_clamped_magnitude() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(definition(StatusKind::Slow, 1.5, 1.0));
+
+        assert_eq!(
+            status_effects.modifier(StatusKind::Slow),
+            Some(StatModifier {
+                source: ModifierSource::Status(StatusKind::Slow),
+                stat: Stat::MoveSpeed,
+                value: ModifierValue::Multiply(0.0),
+            })
+        );
+    }
+}
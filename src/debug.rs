@@ -2,18 +2,25 @@ use bevy::{
     input::{keyboard::KeyboardInput, mouse::MouseButtonInput, ButtonState},
     prelude::*,
 };
-use bevy_xpbd_2d::components::{Collider, CollisionLayers};
+use bevy_xpbd_2d::components::Collider;
 
 use crate::{
-    castle::AllyCastle,
+    castle::{
+        economy::{GoldTransaction, PurchaseRejected},
+        tower::TowerDestroyed,
+        AllyCastle,
+    },
     common::attributes::{ApplyHealthDelta, Health},
-    common::Faction,
-    loading::TextureAssets,
-    physics::hit_detection::{HitBox, HitBoxBundle, HitBoxKind, HurtBoxBundle},
+    common::damage::DamageDealt,
+    common::{viewport_to_world_position, Faction},
+    loading::AudioAssets,
     physics::PhysicsCollisionBundle,
+    session::MatchEntity,
     units::{
-        behaviour::{Behaviour, DefaultBehaviour, EnemyFinderBundle},
-        AttackCooldown, UnitKind,
+        behaviour::{Behaviour, DefaultBehaviour},
+        death::UnitDied,
+        definition::{UnitDefinition, UnitRegistry},
+        insert_combat, AttackCooldown, UnitKind,
     },
     GameState,
 };
@@ -30,6 +37,11 @@ impl Plugin for DebugPlugin {
                 Update,
                 (
                     debug_events::<ApplyHealthDelta>,
+                    debug_events::<DamageDealt>,
+                    debug_events::<UnitDied>,
+                    debug_events::<TowerDestroyed>,
+                    debug_events::<GoldTransaction>,
+                    debug_events::<PurchaseRejected>,
                     emit_spawn_action_mouse,
                     emit_queue_enemy_unit,
                     spawn_enemy,
@@ -48,17 +60,6 @@ where
     }
 }
 
-fn viewport_to_world_position(
-    window: &Window,
-    camera: &Camera,
-    camera_transform: &GlobalTransform,
-) -> Option<Vec2> {
-    window
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-        .map(|ray| ray.origin.truncate())
-}
-
 #[derive(Debug, Event)]
 pub struct SpawnEnemy {
     pub translation: Vec3,
@@ -111,71 +112,52 @@ fn spawn_unit(
     commands: &mut Commands,
     faction: Faction,
     translation: Vec3,
-    textures: &Res<TextureAssets>,
+    definition: &UnitDefinition,
+    hit_sound: &Handle<AudioSource>,
     behaviour: Behaviour,
     default_behaviour: Option<DefaultBehaviour>,
 ) {
+    let stats = definition.stats();
     let entity = commands
         .spawn(SpriteBundle {
             sprite: Sprite {
-                custom_size: Some(Vec2::new(20.0, 20.0)),
+                custom_size: Some(Vec2::splat(definition.size)),
                 ..Default::default()
             },
-            texture: textures.enemy_soldier.clone(),
+            texture: definition.textures.get(&faction),
             transform: Transform::from_translation(translation),
             ..Default::default()
         })
         .insert(PhysicsCollisionBundle {
-            collider: Collider::ball(10.0),
+            collider: Collider::ball(definition.collider),
             ..Default::default()
         })
         .insert(behaviour)
-        .insert(Health::new(100.0))
-        .with_children(|children| {
-            children.spawn(HurtBoxBundle {
-                collider: Collider::ball(9.0),
-                collisionlayers: CollisionLayers::new(
-                    [faction.hurt_layer()],
-                    [faction.opposite().hit_layer()],
-                ),
-                ..Default::default()
-            });
-            children
-                .spawn(HitBoxBundle {
-                    hitbox: HitBox {
-                        damage: 10.0,
-                        kind: HitBoxKind::Once(vec![]),
-                    },
-                    collider: Collider::ball(12.0),
-                    collisionlayers: CollisionLayers::new(
-                        [faction.hit_layer()],
-                        [faction.opposite().hurt_layer()],
-                    ),
-                    ..Default::default()
-                })
-                .insert(AttackCooldown {
-                    timer: Timer::from_seconds(1.0, TimerMode::Repeating),
-                });
-            children.spawn(EnemyFinderBundle {
-                collider: Collider::ball(60.0),
-                collisionlayers: CollisionLayers::new(
-                    [faction.hit_layer()],
-                    [faction.opposite().hurt_layer()],
-                ),
-                ..Default::default()
-            });
-        })
+        .insert(Health::new(definition.health))
+        .insert(stats.clone())
+        .insert(MatchEntity)
         .id();
 
+    let mut entity_commands = commands.entity(entity);
+    insert_combat(
+        &mut entity_commands,
+        faction,
+        definition,
+        &stats,
+        AttackCooldown::new(definition.attack_cooldown),
+        hit_sound,
+    );
+
     if let Some(default_behaviour) = default_behaviour {
-        commands.entity(entity).insert(default_behaviour);
+        entity_commands.insert(default_behaviour);
     }
 }
 
 fn spawn_enemy(
     mut commands: Commands,
     mut spawnenemy_evr: EventReader<SpawnEnemy>,
-    textures: Res<TextureAssets>,
+    unit_registry: UnitRegistry,
+    audio_assets: Res<AudioAssets>,
     castle: Res<AllyCastle>,
 ) {
     for ev in spawnenemy_evr.read() {
@@ -184,7 +166,8 @@ fn spawn_enemy(
             &mut commands,
             Faction::Enemy,
             ev.translation,
-            &textures,
+            unit_registry.get(&UnitKind::Soldier),
+            &audio_assets.hit,
             Behaviour::MoveAndAttack(castle.0.unwrap()),
             Some(DefaultBehaviour(Behaviour::MoveAndAttack(
                 castle.0.unwrap(),
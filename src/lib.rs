@@ -2,25 +2,39 @@
 
 mod audio;
 mod castle;
+mod click;
+pub mod command;
 mod common;
 mod debug;
 mod loading;
 mod physics;
+mod save;
+mod session;
+pub mod simulation;
+mod spell;
 mod ui;
 mod units;
 
 use crate::audio::InternalAudioPlugin;
 use crate::castle::CastlePlugin;
+use crate::click::ClickPlugin;
+use crate::command::replay::{Recording, Replay};
+use crate::command::PlayerCommandPlugin;
+use crate::common::rng::MatchSeed;
 use crate::loading::LoadingPlugin;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::ecs::schedule::ExecutorKind;
 use bevy::prelude::*;
 use common::CommonPlugin;
 #[cfg(debug_assertions)]
 use debug::DebugPlugin;
 use physics::InternalPhysicsPlugin;
+use save::SavePlugin;
+use session::SessionPlugin;
+use spell::SpellPlugin;
 use ui::InternalUiPlugin;
 use units::UnitPluging;
 
@@ -34,6 +48,8 @@ enum GameState {
     Loading,
     // During this State the actual game logic is executed
     Playing,
+    // The match is frozen and the pause menu is shown
+    Paused,
     // Here the menu is drawn and waiting for player interaction
     Menu,
     // Instructions
@@ -44,19 +60,55 @@ enum GameState {
     Won,
 }
 
-pub struct GamePlugin;
+/// Ticks per second of `FixedUpdate`, which runs the whole game simulation
+pub const FIXED_TIMESTEP_HZ: f64 = 60.0;
+
+#[derive(Default)]
+pub struct GamePlugin {
+    /// Run without window, rendering, audio and ui, e.g. for the simulation
+    pub headless: bool,
+    /// Play back this recording instead of taking player input
+    pub replay: Option<Recording>,
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        /* Gameplay runs single threaded in FixedUpdate, so system order and entity ids are reproducible */
+        app.insert_resource(Time::<Fixed>::from_hz(FIXED_TIMESTEP_HZ))
+            .edit_schedule(FixedUpdate, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+
         app.add_state::<GameState>().add_plugins((
-            LoadingPlugin,
-            InternalUiPlugin,
-            InternalAudioPlugin,
+            LoadingPlugin {
+                headless: self.headless,
+            },
+            SessionPlugin,
             CastlePlugin,
+            ClickPlugin,
+            PlayerCommandPlugin,
             UnitPluging,
+            SpellPlugin,
             CommonPlugin,
-            InternalPhysicsPlugin,
+            InternalPhysicsPlugin {
+                headless: self.headless,
+            },
         ));
+
+        if let Some(recording) = &self.replay {
+            app.insert_resource(MatchSeed(Some(recording.seed)))
+                .insert_resource(recording.settings)
+                .insert_resource(Replay::new(recording.clone()));
+        }
+
+        if self.headless {
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(command::replay::RecorderPlugin);
+
+        app.add_plugins((InternalUiPlugin, InternalAudioPlugin, SavePlugin));
         #[cfg(debug_assertions)]
         {
             app.add_plugins((
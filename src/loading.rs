@@ -1,27 +1,55 @@
+use std::marker::PhantomData;
+
+use crate::castle::economy::Economy;
+use crate::castle::spawner::WaveSchedule;
+use crate::castle::tech::TechTree;
+use crate::units::definition::UnitDefinitions;
 use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
 use bevy_asset_loader::prelude::*;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
 
-pub struct LoadingPlugin;
+pub struct LoadingPlugin {
+    /// Skips audio and ui assets, which require a window and an audio device
+    pub headless: bool,
+}
 
 /// This plugin loads all assets using [`AssetLoader`] from a third party bevy plugin
 /// Alternatively you can write the logic to load assets yourself
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loading_state(
-            LoadingState::new(GameState::Loading).continue_to_state(GameState::Menu),
-        )
-        .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, UiAssets>(GameState::Loading);
+        app.init_asset::<UnitDefinitions>()
+            .init_asset_loader::<RonAssetLoader<UnitDefinitions>>()
+            .init_asset::<WaveSchedule>()
+            .init_asset_loader::<RonAssetLoader<WaveSchedule>>()
+            .init_asset::<TechTree>()
+            .init_asset_loader::<RonAssetLoader<TechTree>>()
+            .init_asset::<Economy>()
+            .init_asset_loader::<RonAssetLoader<Economy>>()
+            .add_loading_state(
+                LoadingState::new(GameState::Loading).continue_to_state(GameState::Menu),
+            )
+            .add_collection_to_loading_state::<_, DataAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+
+        if self.headless {
+            app.init_resource::<AudioAssets>();
+        } else {
+            app.add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
+                .add_collection_to_loading_state::<_, UiAssets>(GameState::Loading);
+        }
     }
 }
 
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see <https://github.com/NiklasEi/bevy_asset_loader>)
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct AudioAssets {
     #[asset(path = "audio/Abstraction-Three-Red-Hearts-Go-_No-Vocal_.ogg")]
     pub go: Handle<AudioSource>,
@@ -35,10 +63,6 @@ pub struct AudioAssets {
 pub struct TextureAssets {
     #[asset(path = "textures/castle.png")]
     pub castle: Handle<Image>,
-    #[asset(path = "textures/ally_soldier.png")]
-    pub ally_soldier: Handle<Image>,
-    #[asset(path = "textures/enemy_soldier.png")]
-    pub enemy_soldier: Handle<Image>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -58,3 +82,67 @@ pub struct UiAssets {
     #[asset(path = "textures/background.png")]
     pub background: Handle<Image>,
 }
+
+/// Game data like unit stats, which designers can tune without recompiling
+/// With the `dev` feature these files are hot-reloaded
+#[derive(AssetCollection, Resource)]
+pub struct DataAssets {
+    #[asset(path = "data/game.units.ron")]
+    pub units: Handle<UnitDefinitions>,
+    #[asset(path = "data/game.waves.ron")]
+    pub waves: Handle<WaveSchedule>,
+    #[asset(path = "data/game.tech.ron")]
+    pub tech: Handle<TechTree>,
+    #[asset(path = "data/game.economy.ron")]
+    pub economy: Handle<Economy>,
+}
+
+/// An asset that is deserialized from a RON file by [`RonAssetLoader`]
+pub trait RonAsset: Asset + DeserializeOwned {
+    /// File extensions the loader is registered for, e.g. `units.ron`
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Load assets referenced by path in the deserialized data, e.g. textures
+    fn load_dependencies(&mut self, _load_context: &mut LoadContext) {}
+}
+
+pub struct RonAssetLoader<A>(PhantomData<fn() -> A>);
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RonAssetLoaderError {
+    #[error("Could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let mut asset: A = ron::de::from_bytes(&bytes)?;
+            asset.load_dependencies(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        A::EXTENSIONS
+    }
+}
@@ -6,11 +6,21 @@ use bevy::prelude::*;
 use bevy::window::{PrimaryWindow, WindowResolution};
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
+use bevy_jam4_click_defense::command::replay::Recording;
 use bevy_jam4_click_defense::GamePlugin;
 use std::io::Cursor;
 use winit::window::Icon;
 
 fn main() {
+    // `--replay <path>` plays back a recorded match instead of taking input
+    let replay = std::env::args()
+        .skip_while(|arg| arg != "--replay")
+        .nth(1)
+        .map(|path| {
+            Recording::load(&path)
+                .unwrap_or_else(|error| panic!("Could not load replay {}: {}", path, error))
+        });
+
     App::new()
         .insert_resource(AssetMetaCheck::Never)
         .insert_resource(Msaa::Off)
@@ -30,7 +40,10 @@ fn main() {
             }),
             ..default()
         }))
-        .add_plugins(GamePlugin)
+        .add_plugins(GamePlugin {
+            replay,
+            ..default()
+        })
         .add_systems(Startup, set_window_icon)
         .run();
 }
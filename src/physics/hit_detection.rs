@@ -3,21 +3,32 @@ use bevy_xpbd_2d::components::{
     Collider, ColliderParent, CollidingEntities, CollisionLayers, Sensor,
 };
 
-use crate::common::attributes::ApplyHealthDelta;
+use crate::{
+    command::SimulationSet,
+    common::{
+        attributes::HealthDeltaSource,
+        damage::{Damage, DealDamage},
+        status::{ApplyStatusEffect, StatusEffectDefinition, StatusEffects},
+    },
+};
 
 pub struct HitDetectionPlugin;
 
 // This plugin is responsible to control the game audio
 impl Plugin for HitDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, hit_detection);
+        app.add_systems(FixedUpdate, hit_detection.in_set(SimulationSet::Combat));
     }
 }
 
 #[derive(Debug, Default, Component)]
 pub struct HitBox {
-    pub damage: f32,
+    pub damage: Damage,
     pub kind: HitBoxKind,
+    /// Credited for the damage, the unit that attacked or fired the projectile
+    pub source: Option<HealthDeltaSource>,
+    /// Applied to everything that is hit
+    pub status_effect: Option<StatusEffectDefinition>,
 }
 
 impl HitBox {
@@ -34,6 +45,13 @@ impl HitBox {
             HitBoxKind::Persistent => false,
         }
     }
+
+    pub fn has_hit(&self) -> bool {
+        match &self.kind {
+            HitBoxKind::Once(vec) => !vec.is_empty(),
+            HitBoxKind::Persistent => false,
+        }
+    }
 }
 
 #[derive(Debug, Default, Component)]
@@ -62,12 +80,27 @@ pub struct HurtBoxBundle {
     pub collisionlayers: CollisionLayers,
 }
 
-fn hit_detection(
-    mut hit_boxes: Query<(&ColliderParent, &CollidingEntities, &mut HitBox, &AudioSink)>,
+pub fn hit_detection(
+    mut hit_boxes: Query<(
+        &ColliderParent,
+        &CollidingEntities,
+        &mut HitBox,
+        Option<&AudioSink>,
+    )>,
     hurt_boxes: Query<&ColliderParent, With<HurtBox>>,
-    mut applyhealthdelta_evw: EventWriter<ApplyHealthDelta>,
+    status_effects: Query<&StatusEffects>,
+    mut dealdamage_evw: EventWriter<DealDamage>,
+    mut applystatuseffect_evw: EventWriter<ApplyStatusEffect>,
 ) {
     for (parent, colliding_entities, mut hitbox, audio_sink) in &mut hit_boxes {
+        /* Stunned units do not hit anything */
+        if status_effects
+            .get(parent.get())
+            .is_ok_and(|status_effects| status_effects.is_stunned())
+        {
+            continue;
+        }
+
         let mut colliding_entities: Vec<Entity> = colliding_entities
             .iter()
             .filter_map(|entity| {
@@ -91,15 +124,24 @@ fn hit_detection(
                 colliding_entities
             );
 
-            audio_sink.stop();
-            audio_sink.play();
+            if let Some(audio_sink) = audio_sink {
+                audio_sink.stop();
+                audio_sink.play();
+            }
 
-            applyhealthdelta_evw.send_batch(colliding_entities.iter().map(|entity| {
-                ApplyHealthDelta {
-                    entity: *entity,
-                    delta: -hitbox.damage,
-                }
+            dealdamage_evw.send_batch(colliding_entities.iter().map(|entity| DealDamage {
+                target: *entity,
+                damage: hitbox.damage,
+                source: hitbox.source,
             }));
+            if let Some(effect) = hitbox.status_effect {
+                applystatuseffect_evw.send_batch(colliding_entities.iter().map(|entity| {
+                    ApplyStatusEffect {
+                        entity: *entity,
+                        effect,
+                    }
+                }));
+            }
 
             match hitbox.kind {
                 HitBoxKind::Once(ref mut vec) => vec.append(&mut colliding_entities),
@@ -6,21 +6,28 @@ use bevy_xpbd_2d::plugins::PhysicsDebugPlugin;
 use bevy_xpbd_2d::{
     components::{Collider, CollisionLayers, RigidBody},
     plugins::PhysicsPlugins,
-    prelude::PhysicsLayer,
+    prelude::{Physics, PhysicsLayer},
     resources::Gravity,
 };
 
+use crate::FIXED_TIMESTEP_HZ;
+
 use self::hit_detection::HitDetectionPlugin;
 
-pub struct InternalPhysicsPlugin;
+pub struct InternalPhysicsPlugin {
+    /// Skips the debug rendering of colliders
+    pub headless: bool,
+}
 
 // This plugin is responsible to control the game audio
 impl Plugin for InternalPhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((PhysicsPlugins::default(), HitDetectionPlugin))
+        /* Physics steps once per FixedUpdate, which already provides the fixed timestep */
+        app.add_plugins((PhysicsPlugins::new(FixedUpdate), HitDetectionPlugin))
+            .insert_resource(Time::new_with(Physics::fixed_once_hz(FIXED_TIMESTEP_HZ)))
             .insert_resource(Gravity::ZERO);
         #[cfg(debug_assertions)]
-        {
+        if !self.headless {
             app.add_plugins(PhysicsDebugPlugin::default());
         }
     }
@@ -0,0 +1,863 @@
+pub mod storage;
+
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use bevy_xpbd_2d::components::{LinearVelocity, Sensor};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    castle::{
+        commander::{Commander, Spending, Strategy},
+        economy::{Buildings, EconomyRegistry, GoldLedger, IncomeTimer},
+        spawner::{Wave, WaveSchedule},
+        tech::{Research, ResearchCompleted, TechRegistry, TechTree},
+        tower::{spawn_tower, Tower, TowerSlots},
+        upgrade::SpawnCooldownReduction,
+        AllyCastle, Castle, EnemyCastle, Gold, SpawnQueue,
+    },
+    click::ClickDamageUpgrade,
+    common::{
+        attributes::Health,
+        stats::{ModifierSource, ModifierValue, Stat, StatModifier, Stats},
+        status::{StatusEffect, StatusEffects, StatusKind},
+        Faction,
+    },
+    loading::{AudioAssets, DataAssets, TextureAssets},
+    session::{MatchSettings, MatchSetupSet},
+    spell::Mana,
+    units::{
+        behaviour::{Behaviour, DefaultBehaviour},
+        definition::UnitRegistry,
+        insert_combat,
+        rally::RallyPoint,
+        spawn_unit_body,
+        upgrade::{AttackCooldownUpgrade, ShieldUpgrade},
+        AttackCooldown, SpawnProtection, UnitKind,
+    },
+    GameState,
+};
+
+use self::storage::StorageError;
+
+pub struct SavePlugin;
+
+/// This plugin handles saving the running match and restoring it via the "Continue" button in the menu
+/// The match is saved every few seconds, when pausing and on exit, the save is deleted once the match is over
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutosaveTimer>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    reset_autosave_timer.in_set(MatchSetupSet),
+                    (apply_deferred, restore_resources, restore_match)
+                        .chain()
+                        .after(MatchSetupSet)
+                        .run_if(resource_exists::<RestoreMatch>()),
+                ),
+            )
+            .add_systems(Update, autosave.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Paused), save_match)
+            .add_systems(
+                Last,
+                save_on_exit
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+            )
+            .add_systems(OnEnter(GameState::GameOver), delete_save)
+            .add_systems(OnEnter(GameState::Won), delete_save);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("Could not parse save game: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize save game: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+/// Everything needed to resume a match
+/// Projectiles and spell effects are short lived and not saved
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveGame {
+    gold: usize,
+    wave: SavedWave,
+    ally_castle: SavedCastle,
+    enemy_castle: SavedCastle,
+    units: Vec<SavedUnit>,
+    #[serde(default)]
+    rally_point: Option<[f32; 2]>,
+    #[serde(default)]
+    mana: f32,
+    #[serde(default)]
+    commander: Option<SavedCommander>,
+    /// Older save games continue with the settings chosen in the menu
+    #[serde(default)]
+    settings: Option<MatchSettings>,
+    /// Elapsed seconds until the next income tick
+    #[serde(default)]
+    income_elapsed: f32,
+    #[serde(default)]
+    ledger: GoldLedger,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedCommander {
+    gold: usize,
+    strategy: Strategy,
+    spent: HashMap<Spending, usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedWave {
+    level: u32,
+    /// Elapsed seconds of the delay until the wave starts
+    elapsed: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedCastle {
+    health: f32,
+    spawn_queue: Vec<UnitKind>,
+    spawn_interval: f32,
+    /// Elapsed seconds until the next queued unit spawns
+    spawn_elapsed: f32,
+    spawn_cooldown_reduction: usize,
+    shield: usize,
+    attack_cooldown: usize,
+    #[serde(default)]
+    click_damage: usize,
+    towers: Vec<SavedTower>,
+    /// Ids of the researched nodes of the tech tree
+    #[serde(default)]
+    research: Vec<String>,
+    /// Number of economic buildings by id
+    #[serde(default)]
+    buildings: HashMap<String, usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedTower {
+    slot: usize,
+    health: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedUnit {
+    kind: UnitKind,
+    faction: Faction,
+    translation: [f32; 3],
+    health: f32,
+    max_health: f32,
+    behaviour: SavedBehaviour,
+    default_behaviour: Option<SavedBehaviour>,
+    /// Units still under spawn protection have no combat components yet
+    spawn_protected: bool,
+    /// Elapsed seconds of the attack cooldown
+    attack_cooldown: f32,
+    #[serde(default)]
+    status_effects: Vec<StatusEffect>,
+    /// Modifiers of upgrades and buffs on top of the stats of the unit kind
+    #[serde(default)]
+    modifiers: Vec<SavedModifier>,
+}
+
+impl SavedUnit {
+    fn stats(&self, unit_registry: &UnitRegistry, tech_tree: &TechTree) -> Stats {
+        let modifiers: Vec<StatModifier> = self
+            .modifiers
+            .iter()
+            .filter_map(|modifier| modifier.restore(tech_tree))
+            .collect();
+        unit_registry
+            .get(&self.kind)
+            .stats()
+            .with_modifiers(modifiers.iter())
+    }
+}
+
+/// [`StatModifier`] with its tech tree node referenced by id, like the research of a castle
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedModifier {
+    source: SavedModifierSource,
+    stat: Stat,
+    value: ModifierValue,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum SavedModifierSource {
+    ShieldUpgrade,
+    AttackCooldownUpgrade,
+    /// Id of the researched node of the tech tree
+    Tech(String),
+    Status(StatusKind),
+}
+
+impl SavedModifier {
+    fn save(modifier: &StatModifier, tech_tree: &TechTree) -> Self {
+        let source = match modifier.source {
+            ModifierSource::ShieldUpgrade => SavedModifierSource::ShieldUpgrade,
+            ModifierSource::AttackCooldownUpgrade => SavedModifierSource::AttackCooldownUpgrade,
+            ModifierSource::Tech(node) => {
+                SavedModifierSource::Tech(tech_tree.nodes()[node].id.clone())
+            }
+            ModifierSource::Status(kind) => SavedModifierSource::Status(kind),
+        };
+        Self {
+            source,
+            stat: modifier.stat,
+            value: modifier.value,
+        }
+    }
+
+    /// `None` if the node is not part of the tech tree anymore
+    fn restore(&self, tech_tree: &TechTree) -> Option<StatModifier> {
+        let source = match &self.source {
+            SavedModifierSource::ShieldUpgrade => ModifierSource::ShieldUpgrade,
+            SavedModifierSource::AttackCooldownUpgrade => ModifierSource::AttackCooldownUpgrade,
+            SavedModifierSource::Tech(id) => ModifierSource::Tech(tech_tree.index_of(id)?),
+            SavedModifierSource::Status(kind) => ModifierSource::Status(*kind),
+        };
+        Some(StatModifier {
+            source,
+            stat: self.stat,
+            value: self.value,
+        })
+    }
+}
+
+/// [`Behaviour`] with its target entity replaced by a reference that survives saving
+#[derive(Debug, Serialize, Deserialize)]
+enum SavedBehaviour {
+    Wandering {
+        duration: f32,
+        elapsed: f32,
+        velocity: [f32; 2],
+    },
+    MoveToPoint([f32; 2]),
+    HoldPoint([f32; 2]),
+    /// `None` if the target was neither a castle, a tower nor a unit
+    MoveAndAttack(Option<SavedTarget>),
+    MoveOrder([f32; 2]),
+    AttackOrder(Option<SavedTarget>),
+    ReturnToCastle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum SavedTarget {
+    Castle(Faction),
+    Tower(Faction, usize),
+    /// Index into `SaveGame::units`
+    Unit(usize),
+}
+
+impl SavedBehaviour {
+    fn save(behaviour: &Behaviour, targets: &HashMap<Entity, SavedTarget>) -> Self {
+        match behaviour {
+            Behaviour::Wandering(timer, velocity) => Self::Wandering {
+                duration: timer.duration().as_secs_f32(),
+                elapsed: timer.elapsed_secs(),
+                velocity: velocity.0.to_array(),
+            },
+            Behaviour::MoveToPoint(point) => Self::MoveToPoint(point.to_array()),
+            Behaviour::HoldPoint(point) => Self::HoldPoint(point.to_array()),
+            Behaviour::MoveAndAttack(target) => Self::MoveAndAttack(targets.get(target).copied()),
+            Behaviour::MoveOrder(point) => Self::MoveOrder(point.to_array()),
+            Behaviour::AttackOrder(target) => Self::AttackOrder(targets.get(target).copied()),
+            Behaviour::ReturnToCastle => Self::ReturnToCastle,
+        }
+    }
+
+    /// Unknown targets become `Entity::PLACEHOLDER`, which makes the unit fall back to its `DefaultBehaviour`
+    fn restore(&self, targets: &HashMap<SavedTarget, Entity>) -> Behaviour {
+        match self {
+            Self::Wandering {
+                duration,
+                elapsed,
+                velocity,
+            } => {
+                let mut timer = Timer::from_seconds(*duration, TimerMode::Repeating);
+                timer.set_elapsed(Duration::from_secs_f32(*elapsed));
+                Behaviour::Wandering(timer, LinearVelocity(Vec2::from_array(*velocity)))
+            }
+            Self::MoveToPoint(point) => Behaviour::MoveToPoint(Vec2::from_array(*point)),
+            Self::HoldPoint(point) => Behaviour::HoldPoint(Vec2::from_array(*point)),
+            Self::MoveAndAttack(target) => {
+                Behaviour::MoveAndAttack(Self::restore_target(target, targets))
+            }
+            Self::MoveOrder(point) => Behaviour::MoveOrder(Vec2::from_array(*point)),
+            Self::AttackOrder(target) => {
+                Behaviour::AttackOrder(Self::restore_target(target, targets))
+            }
+            Self::ReturnToCastle => Behaviour::ReturnToCastle,
+        }
+    }
+
+    fn restore_target(
+        target: &Option<SavedTarget>,
+        targets: &HashMap<SavedTarget, Entity>,
+    ) -> Entity {
+        target
+            .and_then(|target| targets.get(&target).copied())
+            .unwrap_or(Entity::PLACEHOLDER)
+    }
+}
+
+/// Reads the saved match, if there is one
+pub fn load() -> Result<Option<SaveGame>, SaveError> {
+    match storage::read()? {
+        Some(content) => Ok(Some(ron::from_str(&content)?)),
+        None => Ok(None),
+    }
+}
+
+fn store(save_game: &SaveGame) -> Result<(), SaveError> {
+    storage::write(&ron::to_string(save_game)?)?;
+    Ok(())
+}
+
+/// Restores this save game when the match starts
+#[derive(Debug, Resource)]
+pub struct RestoreMatch(pub SaveGame);
+
+#[derive(Debug, Resource)]
+struct AutosaveTimer(Timer);
+
+impl Default for AutosaveTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(10.0, TimerMode::Repeating))
+    }
+}
+
+#[derive(SystemParam)]
+struct MatchQuery<'w, 's> {
+    gold: Res<'w, Gold>,
+    wave: Res<'w, Wave>,
+    ally_castle: Res<'w, AllyCastle>,
+    enemy_castle: Res<'w, EnemyCastle>,
+    rally_point: Res<'w, RallyPoint>,
+    mana: Res<'w, Mana>,
+    commander: Res<'w, Commander>,
+    settings: Res<'w, MatchSettings>,
+    income_timer: Res<'w, IncomeTimer>,
+    ledger: Res<'w, GoldLedger>,
+    castles: Query<
+        'w,
+        's,
+        (
+            &'static Health,
+            &'static SpawnQueue,
+            &'static SpawnCooldownReduction,
+            &'static ShieldUpgrade,
+            &'static AttackCooldownUpgrade,
+            &'static TowerSlots,
+            &'static ClickDamageUpgrade,
+            &'static Research,
+            &'static Buildings,
+        ),
+        With<Castle>,
+    >,
+    towers: Query<'w, 's, &'static Health, With<Tower>>,
+    units: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static UnitKind,
+            &'static Faction,
+            &'static Transform,
+            &'static Health,
+            &'static Behaviour,
+            Option<&'static DefaultBehaviour>,
+            Option<&'static SpawnProtection>,
+            Option<&'static Children>,
+            Option<&'static StatusEffects>,
+            Option<&'static Stats>,
+        ),
+    >,
+    attack_cooldowns: Query<'w, 's, &'static AttackCooldown>,
+    tech_registry: TechRegistry<'w>,
+    economy_registry: EconomyRegistry<'w>,
+}
+
+impl MatchQuery<'_, '_> {
+    fn save_game(&self) -> Option<SaveGame> {
+        let mut targets = HashMap::new();
+        for (faction, castle) in [
+            (Faction::Ally, self.ally_castle.0),
+            (Faction::Enemy, self.enemy_castle.0),
+        ] {
+            let Some(entity) = castle else {
+                continue;
+            };
+            targets.insert(entity, SavedTarget::Castle(faction));
+            if let Ok((_, _, _, _, _, tower_slots, ..)) = self.castles.get(entity) {
+                for (slot, tower) in tower_slots.iter() {
+                    targets.insert(tower, SavedTarget::Tower(faction, slot));
+                }
+            }
+        }
+        for (index, (entity, ..)) in self.units.iter().enumerate() {
+            targets.insert(entity, SavedTarget::Unit(index));
+        }
+
+        let tech_tree = self.tech_registry.get();
+        let units = self
+            .units
+            .iter()
+            .map(
+                |(
+                    entity,
+                    kind,
+                    faction,
+                    transform,
+                    health,
+                    behaviour,
+                    default_behaviour,
+                    spawn_protection,
+                    children,
+                    status_effects,
+                    stats,
+                )| {
+                    /* Ranged units carry their cooldown, melee units on their hitbox */
+                    let attack_cooldown = std::iter::once(entity)
+                        .chain(
+                            children
+                                .into_iter()
+                                .flat_map(|children| children.iter().copied()),
+                        )
+                        .find_map(|entity| self.attack_cooldowns.get(entity).ok())
+                        .map_or(0.0, |cooldown| cooldown.timer.elapsed_secs());
+
+                    SavedUnit {
+                        kind: *kind,
+                        faction: *faction,
+                        translation: transform.translation.to_array(),
+                        health: health.current,
+                        max_health: health.max,
+                        behaviour: SavedBehaviour::save(behaviour, &targets),
+                        default_behaviour: default_behaviour.map(|default_behaviour| {
+                            SavedBehaviour::save(&default_behaviour.0, &targets)
+                        }),
+                        spawn_protected: spawn_protection.is_some(),
+                        attack_cooldown,
+                        status_effects: status_effects
+                            .map_or(vec![], |status_effects| status_effects.0.clone()),
+                        modifiers: stats.map_or(vec![], |stats| {
+                            stats
+                                .modifiers
+                                .iter()
+                                .map(|modifier| SavedModifier::save(modifier, tech_tree))
+                                .collect()
+                        }),
+                    }
+                },
+            )
+            .collect();
+
+        Some(SaveGame {
+            gold: self.gold.0,
+            wave: SavedWave {
+                level: self.wave.level,
+                elapsed: self.wave.timer.elapsed_secs(),
+            },
+            ally_castle: self.save_castle(self.ally_castle.0?)?,
+            enemy_castle: self.save_castle(self.enemy_castle.0?)?,
+            units,
+            rally_point: self.rally_point.0.map(|point| point.to_array()),
+            mana: self.mana.current,
+            commander: Some(SavedCommander {
+                gold: self.commander.gold,
+                strategy: self.commander.strategy,
+                spent: self.commander.spent.clone(),
+            }),
+            settings: Some(*self.settings),
+            income_elapsed: self.income_timer.0.elapsed_secs(),
+            ledger: self.ledger.clone(),
+        })
+    }
+
+    fn save_castle(&self, entity: Entity) -> Option<SavedCastle> {
+        let (
+            health,
+            spawn_queue,
+            spawn_cooldown_reduction,
+            shield,
+            attack_cooldown,
+            tower_slots,
+            click_damage,
+            research,
+            buildings,
+        ) = self.castles.get(entity).ok()?;
+        let tech_tree = self.tech_registry.get();
+        let economy = self.economy_registry.get();
+
+        Some(SavedCastle {
+            health: health.current,
+            spawn_queue: spawn_queue.kinds().collect(),
+            spawn_interval: spawn_queue.interval,
+            spawn_elapsed: spawn_queue.timer.elapsed_secs(),
+            spawn_cooldown_reduction: spawn_cooldown_reduction.level(),
+            shield: shield.level(),
+            attack_cooldown: attack_cooldown.level(),
+            click_damage: click_damage.level(),
+            towers: tower_slots
+                .iter()
+                .filter_map(|(slot, tower)| {
+                    let health = self.towers.get(tower).ok()?;
+                    Some(SavedTower {
+                        slot,
+                        health: health.current,
+                    })
+                })
+                .collect(),
+            research: research
+                .iter()
+                .map(|node| tech_tree.nodes()[node].id.clone())
+                .collect(),
+            buildings: buildings
+                .iter()
+                .map(|(index, count)| (economy.buildings()[index].id.clone(), count))
+                .collect(),
+        })
+    }
+
+    fn save(&self) {
+        let Some(save_game) = self.save_game() else {
+            return;
+        };
+        if let Err(error) = store(&save_game) {
+            warn!("Could not save match: {}", error);
+        }
+    }
+}
+
+fn reset_autosave_timer(mut autosave_timer: ResMut<AutosaveTimer>) {
+    autosave_timer.0.reset();
+}
+
+fn autosave(time: Res<Time>, mut autosave_timer: ResMut<AutosaveTimer>, match_query: MatchQuery) {
+    if autosave_timer.0.tick(time.delta()).just_finished() {
+        match_query.save();
+    }
+}
+
+fn save_match(match_query: MatchQuery) {
+    match_query.save();
+}
+
+fn save_on_exit(mut appexit_evr: EventReader<AppExit>, match_query: MatchQuery) {
+    if appexit_evr.read().last().is_some() {
+        match_query.save();
+    }
+}
+
+fn delete_save() {
+    if let Err(error) = storage::remove() {
+        warn!("Could not delete save game: {}", error);
+    }
+}
+
+/// Restores the match resources, runs before `restore_match`, which removes the [`RestoreMatch`]
+fn restore_resources(
+    restore: Res<RestoreMatch>,
+    mut settings: ResMut<MatchSettings>,
+    mut ledger: ResMut<GoldLedger>,
+    mut commander: ResMut<Commander>,
+    mut wave: ResMut<Wave>,
+    data_assets: Res<DataAssets>,
+    wave_schedules: Res<Assets<WaveSchedule>>,
+) {
+    let save_game = &restore.0;
+    *ledger = save_game.ledger.clone();
+    if let Some(saved) = save_game.settings {
+        *settings = saved;
+        *commander = Commander::new(commander.strategy, saved.commander);
+    }
+    if let Some(saved) = &save_game.commander {
+        commander.gold = saved.gold;
+        commander.strategy = saved.strategy;
+        commander.spent = saved.spent.clone();
+    }
+    if let Some(wave_schedule) = wave_schedules.get(&data_assets.waves) {
+        wave.set_level(save_game.wave.level, wave_schedule, &settings);
+        wave.timer
+            .set_elapsed(Duration::from_secs_f32(save_game.wave.elapsed));
+    }
+}
+
+fn restore_match(
+    mut commands: Commands,
+    restore: Res<RestoreMatch>,
+    mut gold: ResMut<Gold>,
+    mut rally_point: ResMut<RallyPoint>,
+    mut mana: ResMut<Mana>,
+    mut income_timer: ResMut<IncomeTimer>,
+    ally_castle: Res<AllyCastle>,
+    enemy_castle: Res<EnemyCastle>,
+    mut castles: Query<
+        (
+            &Transform,
+            &mut Health,
+            &mut SpawnQueue,
+            &mut SpawnCooldownReduction,
+            &mut ShieldUpgrade,
+            &mut AttackCooldownUpgrade,
+            &mut TowerSlots,
+            &mut ClickDamageUpgrade,
+            &mut Research,
+            &mut Buildings,
+        ),
+        With<Castle>,
+    >,
+    settings: Res<MatchSettings>,
+    textures: Res<TextureAssets>,
+    audio_assets: Res<AudioAssets>,
+    unit_registry: UnitRegistry,
+    tech_registry: TechRegistry,
+    economy_registry: EconomyRegistry,
+    mut researchcompleted_evw: EventWriter<ResearchCompleted>,
+) {
+    let save_game = &restore.0;
+    commands.remove_resource::<RestoreMatch>();
+
+    gold.0 = save_game.gold;
+    rally_point.0 = save_game.rally_point.map(Vec2::from_array);
+    mana.current = save_game.mana.min(mana.max);
+    income_timer
+        .0
+        .set_elapsed(Duration::from_secs_f32(save_game.income_elapsed));
+
+    let mut targets = HashMap::new();
+    for (faction, castle, saved) in [
+        (Faction::Ally, ally_castle.0, &save_game.ally_castle),
+        (Faction::Enemy, enemy_castle.0, &save_game.enemy_castle),
+    ] {
+        let Some(entity) = castle else {
+            continue;
+        };
+        let Ok((
+            transform,
+            mut health,
+            mut spawn_queue,
+            mut spawn_cooldown_reduction,
+            mut shield,
+            mut attack_cooldown,
+            mut tower_slots,
+            mut click_damage,
+            mut research,
+            mut buildings,
+        )) = castles.get_mut(entity)
+        else {
+            continue;
+        };
+
+        *health = Health {
+            current: saved.health,
+            max: settings.castle_health,
+        };
+        *spawn_cooldown_reduction = SpawnCooldownReduction::new(saved.spawn_cooldown_reduction);
+        *shield = ShieldUpgrade::new(saved.shield).with_scope(settings.shield_scope);
+        *attack_cooldown = AttackCooldownUpgrade::new(saved.attack_cooldown)
+            .with_scope(settings.attack_cooldown_scope);
+        *click_damage = ClickDamageUpgrade::new(saved.click_damage);
+        for kind in &saved.spawn_queue {
+            spawn_queue.push_back(*kind);
+        }
+        spawn_queue.set_interval(saved.spawn_interval, &spawn_cooldown_reduction);
+        spawn_queue
+            .timer
+            .set_elapsed(Duration::from_secs_f32(saved.spawn_elapsed));
+        targets.insert(SavedTarget::Castle(faction), entity);
+
+        /* Unknown ids stem from an older tech tree and are dropped */
+        let tech_tree = tech_registry.get();
+        for node in saved
+            .research
+            .iter()
+            .filter_map(|id| tech_tree.index_of(id))
+        {
+            research.insert(node);
+            researchcompleted_evw.send(ResearchCompleted {
+                castle: entity,
+                faction,
+                node,
+            });
+        }
+
+        let economy = economy_registry.get();
+        for (index, count) in saved
+            .buildings
+            .iter()
+            .filter_map(|(id, count)| Some((economy.index_of(id)?, *count)))
+        {
+            buildings.add(index, count);
+        }
+
+        for saved_tower in &saved.towers {
+            let tower = spawn_tower(
+                &mut commands,
+                faction,
+                transform.translation.truncate(),
+                saved_tower.slot,
+                &textures,
+            );
+            commands.entity(tower).insert(Health {
+                current: saved_tower.health,
+                max: Tower::HEALTH,
+            });
+            tower_slots.insert(saved_tower.slot, tower);
+            targets.insert(SavedTarget::Tower(faction, saved_tower.slot), tower);
+        }
+    }
+
+    /* Spawn all units first, so behaviours can target units that come later in the list */
+    let tech_tree = tech_registry.get();
+    let units: Vec<Entity> = save_game
+        .units
+        .iter()
+        .map(|saved| {
+            spawn_unit_body(
+                &mut commands,
+                saved.kind,
+                saved.faction,
+                unit_registry.get(&saved.kind),
+                Vec3::from_array(saved.translation),
+                Health {
+                    current: saved.health,
+                    max: saved.max_health,
+                },
+                saved.stats(&unit_registry, tech_tree),
+            )
+            .id()
+        })
+        .collect();
+    for (index, entity) in units.iter().enumerate() {
+        targets.insert(SavedTarget::Unit(index), *entity);
+    }
+
+    for (saved, entity) in save_game.units.iter().zip(units) {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert(saved.behaviour.restore(&targets));
+        if !saved.status_effects.is_empty() {
+            entity_commands.insert(StatusEffects(saved.status_effects.clone()));
+        }
+
+        if saved.spawn_protected {
+            entity_commands
+                .insert(Sensor)
+                .insert(SpawnProtection::default());
+            continue;
+        }
+
+        if let Some(default_behaviour) = &saved.default_behaviour {
+            entity_commands.insert(DefaultBehaviour(default_behaviour.restore(&targets)));
+        }
+
+        let stats = saved.stats(&unit_registry, tech_tree);
+        let mut attack_cooldown = AttackCooldown::new(stats.get(Stat::AttackCooldown));
+        attack_cooldown
+            .timer
+            .set_elapsed(Duration::from_secs_f32(saved.attack_cooldown));
+
+        insert_combat(
+            &mut entity_commands,
+            saved.faction,
+            unit_registry.get(&saved.kind),
+            &stats,
+            attack_cooldown,
+            &audio_assets.hit,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::castle::tech::TechEffect;
+
+    use super::*;
+
+    /// Modifiers `apply_tech_modifiers` pushes when the node with this id is researched
+    fn tech_modifiers(tech_tree: &TechTree, id: &str) -> Vec<StatModifier> {
+        let node = tech_tree.index_of(id).unwrap();
+        tech_tree.nodes()[node]
+            .effects
+            .iter()
+            .filter_map(|effect| match *effect {
+                TechEffect::UnitModifier(stat, value) => Some(StatModifier {
+                    source: ModifierSource::Tech(node),
+                    stat,
+                    value,
+                }),
+                TechEffect::UnlockUnit(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tech_modifiers_are_restored_against_a_changed_tech_tree() {
+        let old_tech_tree: TechTree = ron::from_str(
+            r#"(nodes: [
+                (id: "forge", name: "Forge", cost: 10, effects: [UnitModifier(Armor, Add(10.0))]),
+                (id: "sharpened_blades", name: "Sharpened Blades", cost: 12, requires: ["forge"], effects: [UnitModifier(Damage, Multiply(1.5))]),
+            ])"#,
+        )
+        .unwrap();
+        /* Reordered and extended by two nodes */
+        let new_tech_tree: TechTree = ron::from_str(
+            r#"(nodes: [
+                (id: "archery", name: "Archery Range", cost: 6, effects: [UnlockUnit(Archer)]),
+                (id: "drill", name: "Drill", cost: 12, requires: ["archery"], effects: [UnitModifier(MoveSpeed, Multiply(1.15))]),
+                (id: "sharpened_blades", name: "Sharpened Blades", cost: 12, requires: ["forge"], effects: [UnitModifier(Damage, Multiply(1.5))]),
+                (id: "forge", name: "Forge", cost: 10, effects: [UnitModifier(Armor, Add(10.0))]),
+            ])"#,
+        )
+        .unwrap();
+        let base = [
+            (Stat::Armor, 0.0),
+            (Stat::Damage, 10.0),
+            (Stat::MoveSpeed, 100.0),
+        ];
+
+        let researched = [
+            tech_modifiers(&old_tech_tree, "forge"),
+            tech_modifiers(&old_tech_tree, "sharpened_blades"),
+        ]
+        .concat();
+        let stats = Stats::new(base).with_modifiers(researched.iter());
+        let saved: Vec<SavedModifier> = stats
+            .modifiers
+            .iter()
+            .map(|modifier| SavedModifier::save(modifier, &old_tech_tree))
+            .collect();
+        let saved: Vec<SavedModifier> = ron::from_str(&ron::to_string(&saved).unwrap()).unwrap();
+
+        let expected = [
+            tech_modifiers(&new_tech_tree, "forge"),
+            tech_modifiers(&new_tech_tree, "sharpened_blades"),
+        ]
+        .concat();
+        let restored: Vec<StatModifier> = saved
+            .iter()
+            .filter_map(|modifier| modifier.restore(&new_tech_tree))
+            .collect();
+        assert_eq!(restored, expected);
+
+        let mut stats = Stats::new(base).with_modifiers(restored.iter());
+        assert_eq!(stats.get(Stat::Armor), 10.0);
+        assert_eq!(stats.get(Stat::Damage), 15.0);
+        assert_eq!(stats.get(Stat::MoveSpeed), 100.0);
+
+        /* Restoring the research sends `ResearchCompleted` again, which must not stack the modifiers */
+        for modifier in &expected {
+            stats.modifiers.set(*modifier);
+        }
+        assert_eq!(stats.modifiers.iter().count(), 2);
+        assert_eq!(stats.get(Stat::Armor), 10.0);
+        assert_eq!(stats.get(Stat::Damage), 15.0);
+    }
+}
@@ -0,0 +1,72 @@
+//! Persists the save game as a file on native builds and in the browser local storage on the web
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Could not access save file: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(target_arch = "wasm32")]
+    #[error("Browser local storage is unavailable")]
+    Unavailable,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_PATH: &str = "saves/match.ron";
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read() -> Result<Option<String>, StorageError> {
+    match std::fs::read_to_string(SAVE_PATH) {
+        Ok(content) => Ok(Some(content)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(content: &str) -> Result<(), StorageError> {
+    if let Some(parent) = std::path::Path::new(SAVE_PATH).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(SAVE_PATH, content)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove() -> Result<(), StorageError> {
+    match std::fs::remove_file(SAVE_PATH) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+const SAVE_KEY: &str = "bevy_jam4_click_defense.match";
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, StorageError> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or(StorageError::Unavailable)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read() -> Result<Option<String>, StorageError> {
+    local_storage()?
+        .get_item(SAVE_KEY)
+        .map_err(|_| StorageError::Unavailable)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(content: &str) -> Result<(), StorageError> {
+    local_storage()?
+        .set_item(SAVE_KEY, content)
+        .map_err(|_| StorageError::Unavailable)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove() -> Result<(), StorageError> {
+    local_storage()?
+        .remove_item(SAVE_KEY)
+        .map_err(|_| StorageError::Unavailable)
+}
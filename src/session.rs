@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+};
+use bevy_xpbd_2d::prelude::{Physics, PhysicsTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    castle::{
+        commander::{CommanderParameters, Strategy},
+        spawner::Wave,
+        AllyCastle, EnemyCastle, Gold,
+    },
+    units::upgrade::UpgradeScope,
+    GameState,
+};
+
+pub struct SessionPlugin;
+
+/// This plugin handles the lifecycle of a match: setting it up, pausing, restarting and tearing it down
+/// Resuming from `GameState::Paused` continues the running match instead of setting up a new one
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RestartMatch>()
+            .init_resource::<MatchSettings>()
+            .configure_sets(
+                OnEnter(GameState::Playing),
+                MatchSetupSet.run_if(not(resource_exists::<MatchInProgress>())),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                begin_match.in_set(MatchSetupSet),
+            )
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            .add_systems(OnExit(GameState::Paused), unpause_time)
+            .add_systems(OnEnter(GameState::Menu), teardown_match)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause
+                        .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+                    (teardown_match, start_new_match)
+                        .chain()
+                        .run_if(on_event::<RestartMatch>()),
+                ),
+            );
+    }
+}
+
+/// Systems in `OnEnter(GameState::Playing)` that set up a new match
+/// Skipped when resuming from `GameState::Paused`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct MatchSetupSet;
+
+/// Present from the start of a match until it is torn down
+#[derive(Debug, Resource)]
+pub struct MatchInProgress;
+
+/// Despawned together with its children when the match is torn down
+#[derive(Debug, Default, Component)]
+pub struct MatchEntity;
+
+/// Tears down the current match and starts a new one
+#[derive(Debug, Event)]
+pub struct RestartMatch;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// Any preset with adjusted values
+    Custom,
+}
+
+impl Difficulty {
+    pub const PRESETS: [Self; 3] = [Self::Easy, Self::Normal, Self::Hard];
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Self::Easy),
+            "normal" => Ok(Self::Normal),
+            "hard" => Ok(Self::Hard),
+            _ => Err(format!(
+                "Unknown difficulty {s}, expected one of: easy, normal, hard"
+            )),
+        }
+    }
+}
+
+/// Chosen in the menu before a match starts, read by everything that spawns castles, waves and gold
+/// Part of save games and recordings, so a match always continues with the settings it started with
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+pub struct MatchSettings {
+    pub difficulty: Difficulty,
+    /// Gold of the player when the match starts
+    pub starting_gold: usize,
+    /// Health of both castles
+    pub castle_health: f32,
+    /// Level of the spawn interval, shield and attack upgrades the enemy castle starts with
+    pub enemy_upgrade_level: usize,
+    /// Factor on the gold every wave brings the enemy commander
+    pub wave_gold: f32,
+    /// Factor on the delay between two waves
+    pub wave_delay: f32,
+    pub commander: CommanderParameters,
+    /// A random strategy is picked every match if not set
+    pub enemy_strategy: Option<Strategy>,
+    /// Whether shield upgrades of both castles also apply to the units already on the battlefield
+    #[serde(default)]
+    pub shield_scope: UpgradeScope,
+    /// Whether attack upgrades of both castles also apply to the units already on the battlefield
+    #[serde(default)]
+    pub attack_cooldown_scope: UpgradeScope,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self::preset(Difficulty::Normal)
+    }
+}
+
+impl MatchSettings {
+    /// Settings of the preset, [`Difficulty::Custom`] starts out as [`Difficulty::Normal`]
+    pub fn preset(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => Self {
+                difficulty,
+                starting_gold: 100,
+                castle_health: 1500.0,
+                enemy_upgrade_level: 0,
+                wave_gold: 0.7,
+                wave_delay: 1.2,
+                commander: CommanderParameters {
+                    income: 0.5,
+                    think_interval: 2.0,
+                    counter_chance: 0.0,
+                    max_queued: 20,
+                },
+                enemy_strategy: None,
+                shield_scope: UpgradeScope::AllUnits,
+                attack_cooldown_scope: UpgradeScope::AllUnits,
+            },
+            Difficulty::Normal | Difficulty::Custom => Self {
+                difficulty,
+                starting_gold: 60,
+                castle_health: 1000.0,
+                enemy_upgrade_level: 2,
+                wave_gold: 1.0,
+                wave_delay: 1.0,
+                commander: CommanderParameters {
+                    income: 1.0,
+                    think_interval: 1.0,
+                    counter_chance: 0.5,
+                    max_queued: 40,
+                },
+                enemy_strategy: None,
+                shield_scope: UpgradeScope::AllUnits,
+                attack_cooldown_scope: UpgradeScope::AllUnits,
+            },
+            Difficulty::Hard => Self {
+                difficulty,
+                starting_gold: 40,
+                castle_health: 750.0,
+                enemy_upgrade_level: 4,
+                wave_gold: 1.3,
+                wave_delay: 0.85,
+                commander: CommanderParameters {
+                    income: 1.5,
+                    think_interval: 0.5,
+                    counter_chance: 0.9,
+                    max_queued: 60,
+                },
+                enemy_strategy: None,
+                shield_scope: UpgradeScope::AllUnits,
+                attack_cooldown_scope: UpgradeScope::AllUnits,
+            },
+        }
+    }
+}
+
+fn begin_match(mut commands: Commands) {
+    commands.insert_resource(MatchInProgress);
+}
+
+fn pause_time(mut virtual_time: ResMut<Time<Virtual>>, mut physics_time: ResMut<Time<Physics>>) {
+    virtual_time.pause();
+    physics_time.pause();
+}
+
+fn unpause_time(mut virtual_time: ResMut<Time<Virtual>>, mut physics_time: ResMut<Time<Physics>>) {
+    virtual_time.unpause();
+    physics_time.unpause();
+}
+
+fn toggle_pause(
+    mut keyboard_evr: EventReader<KeyboardInput>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for ev in keyboard_evr.read() {
+        if let Some(KeyCode::Escape) = ev.key_code {
+            if ev.state == ButtonState::Pressed {
+                match state.get() {
+                    GameState::Playing => next_state.set(GameState::Paused),
+                    GameState::Paused => next_state.set(GameState::Playing),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Despawns everything belonging to the match and resets the match resources
+pub fn teardown_match(
+    mut commands: Commands,
+    match_entities: Query<Entity, With<MatchEntity>>,
+    mut gold: ResMut<Gold>,
+    mut wave: ResMut<Wave>,
+    mut ally_castle: ResMut<AllyCastle>,
+    mut enemy_castle: ResMut<EnemyCastle>,
+) {
+    for entity in &match_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *gold = Gold::default();
+    *wave = Wave::default();
+    *ally_castle = AllyCastle::default();
+    *enemy_castle = EnemyCastle::default();
+    commands.remove_resource::<MatchInProgress>();
+}
+
+fn start_new_match(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
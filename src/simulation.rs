@@ -0,0 +1,333 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use bevy::{
+    asset::AssetPlugin, input::InputPlugin, prelude::*, render::texture::ImagePlugin,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    castle::{
+        commander::Commander, economy::GoldLedger, spawner::Wave, AllyCastle, Castle, EnemyCastle,
+        Gold, SpawnUnit,
+    },
+    command::{
+        replay::Recording, CommandLog, PendingPlayerActions, PlayerAction, PlayerCommandSet,
+        SimulationSet,
+    },
+    common::{
+        attributes::Health,
+        rng::{GameRng, MatchSeed},
+        Faction,
+    },
+    units::{death::UnitDied, definition::UnitRegistry, upgrade::ShieldUpgrade, UnitKind},
+    GamePlugin, GameState, FIXED_TIMESTEP_HZ,
+};
+
+pub use crate::{
+    castle::commander::{CommanderParameters, Strategy},
+    session::{Difficulty, MatchSettings},
+    units::upgrade::UpgradeScope,
+};
+
+/// Headless match driven by a [`PlayerPolicy`] instead of a human, used for balance testing
+/// Runs without window, rendering and audio, advancing exactly one fixed tick per update
+pub struct SimulationPlugin {
+    pub policy: PlayerPolicy,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.policy)
+            .init_resource::<SimulationStats>()
+            .add_systems(OnEnter(GameState::Menu), start_match)
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_policy.before(PlayerCommandSet),
+                    record_stats.in_set(SimulationSet::Death),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Scripted stand-in for the player
+#[derive(Debug, Clone, Copy, Resource)]
+pub enum PlayerPolicy {
+    /// Never spends any gold
+    Idle,
+    /// Queues a soldier whenever affordable
+    Soldiers,
+    /// Buys a shield upgrade whenever affordable, queues soldiers otherwise
+    ShieldThenSoldiers,
+}
+
+impl FromStr for PlayerPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "idle" => Ok(Self::Idle),
+            "soldiers" => Ok(Self::Soldiers),
+            "shield-then-soldiers" => Ok(Self::ShieldThenSoldiers),
+            _ => Err(format!(
+                "Unknown policy {s}, expected one of: idle, soldiers, shield-then-soldiers"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub policy: PlayerPolicy,
+    /// Seed of the match, the same seed and policy always produce the same summary
+    pub seed: u64,
+    /// Plays back a recorded match instead, which overrides `policy` and `seed`
+    pub replay: Option<Recording>,
+    /// Simulated time per update
+    pub timestep: Duration,
+    /// The match is aborted after this much simulated time
+    pub max_duration: Duration,
+    /// Difficulty and enemy strategy, a replay uses the settings it was recorded with
+    pub settings: MatchSettings,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            policy: PlayerPolicy::ShieldThenSoldiers,
+            seed: 0,
+            replay: None,
+            timestep: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
+            max_duration: Duration::from_secs(60 * 60),
+            settings: MatchSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    GameOver,
+    /// `SimulationConfig::max_duration` was reached
+    Timeout,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerFaction {
+    pub ally: usize,
+    pub enemy: usize,
+}
+
+impl PerFaction {
+    fn get_mut(&mut self, faction: &Faction) -> &mut usize {
+        match faction {
+            Faction::Ally => &mut self.ally,
+            Faction::Enemy => &mut self.enemy,
+        }
+    }
+}
+
+/// Castle health at a point in simulated time
+#[derive(Debug, Clone, Copy)]
+pub struct CastleHealthSample {
+    pub seconds: f32,
+    pub ally: f32,
+    pub enemy: f32,
+}
+
+#[derive(Debug, Default, Clone, Resource)]
+pub struct SimulationStats {
+    /// Filled in from the gold ledger once the match is over
+    pub gold_earned: usize,
+    pub gold_spent: usize,
+    pub units_spawned: PerFaction,
+    pub units_killed: PerFaction,
+    pub castle_health: Vec<CastleHealthSample>,
+    seconds: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationSummary {
+    pub seed: u64,
+    pub outcome: Outcome,
+    pub wave: u32,
+    pub enemy_strategy: Strategy,
+    pub duration: Duration,
+    pub stats: SimulationStats,
+    /// The match as played, to be written to a file or replayed
+    pub recording: Recording,
+}
+
+impl Display for SimulationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Seed: {}", self.seed)?;
+        writeln!(f, "Outcome: {:?}", self.outcome)?;
+        writeln!(f, "Wave reached: {}", self.wave)?;
+        writeln!(f, "Difficulty: {:?}", self.recording.settings.difficulty)?;
+        writeln!(f, "Enemy strategy: {:?}", self.enemy_strategy)?;
+        writeln!(f, "Duration: {:.1}s", self.duration.as_secs_f32())?;
+        writeln!(
+            f,
+            "Gold earned/spent: {}/{}",
+            self.stats.gold_earned, self.stats.gold_spent
+        )?;
+        writeln!(
+            f,
+            "Units spawned (ally/enemy): {}/{}",
+            self.stats.units_spawned.ally, self.stats.units_spawned.enemy
+        )?;
+        writeln!(
+            f,
+            "Units killed (ally/enemy): {}/{}",
+            self.stats.units_killed.ally, self.stats.units_killed.enemy
+        )?;
+        writeln!(f, "Castle health (time: ally/enemy):")?;
+        for sample in &self.stats.castle_health {
+            writeln!(
+                f,
+                "  {:>6.0}s: {:.0}/{:.0}",
+                sample.seconds, sample.ally, sample.enemy
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a whole match headless and returns its summary
+pub fn run_simulation(config: SimulationConfig) -> SimulationSummary {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ImagePlugin::default(),
+        /* Nobody presses anything, but the input systems still need their resources */
+        InputPlugin,
+        TransformPlugin,
+        HierarchyPlugin,
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(config.timestep))
+    .insert_resource(MatchSeed(Some(config.seed)))
+    .insert_resource(config.settings)
+    .add_plugins((
+        GamePlugin {
+            headless: true,
+            replay: config.replay.clone(),
+        },
+        SimulationPlugin {
+            policy: match config.replay {
+                Some(_) => PlayerPolicy::Idle,
+                None => config.policy,
+            },
+        },
+    ));
+    app.finish();
+    app.cleanup();
+
+    let mut duration = Duration::ZERO;
+    let outcome = loop {
+        app.update();
+
+        match app.world.resource::<State<GameState>>().get() {
+            GameState::Won => break Outcome::Won,
+            GameState::GameOver => break Outcome::GameOver,
+            GameState::Playing => {
+                duration += config.timestep;
+                if duration >= config.max_duration {
+                    break Outcome::Timeout;
+                }
+            }
+            _ => {}
+        }
+    };
+
+    let seed = app.world.resource::<GameRng>().seed();
+    let mut stats = app.world.resource::<SimulationStats>().clone();
+    let ledger = app.world.resource::<GoldLedger>();
+    stats.gold_earned = ledger.total_earned();
+    stats.gold_spent = ledger.total_spent();
+
+    SimulationSummary {
+        seed,
+        outcome,
+        wave: app.world.resource::<Wave>().level,
+        enemy_strategy: app.world.resource::<Commander>().strategy,
+        duration,
+        stats,
+        recording: Recording {
+            seed,
+            settings: *app.world.resource::<MatchSettings>(),
+            commands: app.world.resource::<CommandLog>().to_vec(),
+        },
+    }
+}
+
+fn start_match(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+fn apply_policy(
+    policy: Res<PlayerPolicy>,
+    ally_castle: Res<AllyCastle>,
+    soldier_shields: Query<&ShieldUpgrade, With<Castle>>,
+    gold: Res<Gold>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+    unit_registry: UnitRegistry,
+) {
+    let Some(entity) = ally_castle.0 else {
+        return;
+    };
+    let soldier_cost = unit_registry.get(&UnitKind::Soldier).cost;
+
+    match *policy {
+        PlayerPolicy::Idle => {}
+        PlayerPolicy::Soldiers => {
+            if gold.0 >= soldier_cost {
+                player_actions.send(PlayerAction::QueueUnit(UnitKind::Soldier));
+            }
+        }
+        PlayerPolicy::ShieldThenSoldiers => {
+            if let Ok(soldier_shield) = soldier_shields.get(entity) {
+                if gold.0 >= soldier_shield.cost() && !soldier_shield.is_max_level() {
+                    player_actions.send(PlayerAction::UpgradeShield);
+                } else if gold.0 >= soldier_cost {
+                    player_actions.send(PlayerAction::QueueUnit(UnitKind::Soldier));
+                }
+            }
+        }
+    }
+}
+
+fn record_stats(
+    time: Res<Time>,
+    mut stats: ResMut<SimulationStats>,
+    mut spawnunit_evr: EventReader<SpawnUnit>,
+    mut unitdied_evr: EventReader<UnitDied>,
+    castle_healths: Query<&Health, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    enemy_castle: Res<EnemyCastle>,
+) {
+    for ev in spawnunit_evr.read() {
+        *stats.units_spawned.get_mut(&ev.faction) += 1;
+    }
+
+    for ev in unitdied_evr.read() {
+        *stats.units_killed.get_mut(&ev.faction) += 1;
+    }
+
+    /* Sample castle health every 5 seconds */
+    let seconds = stats.seconds + time.delta_seconds();
+    if stats.castle_health.is_empty() || (seconds / 5.0).floor() > (stats.seconds / 5.0).floor() {
+        let castle_health = |entity: Option<Entity>| {
+            entity
+                .and_then(|entity| castle_healths.get(entity).ok())
+                .map_or(0.0, |health| health.current)
+        };
+        stats.castle_health.push(CastleHealthSample {
+            seconds,
+            ally: castle_health(ally_castle.0),
+            enemy: castle_health(enemy_castle.0),
+        });
+    }
+    stats.seconds = seconds;
+}
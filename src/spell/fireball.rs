@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::{
+    components::{Collider, ColliderParent},
+    prelude::{SpatialQuery, SpatialQueryFilter},
+};
+
+use crate::{
+    castle::AllyCastle,
+    command::SimulationSet,
+    common::{
+        attributes::HealthDeltaSource,
+        damage::{Damage, DamageType, DealDamage},
+        Faction,
+    },
+    physics::SensorLayers,
+    session::MatchEntity,
+    GameState,
+};
+
+use super::{spell_area_sprite, CastSpell, Spell, SpellCastSet, SpellEffect};
+
+pub struct FireballPlugin;
+
+/// This plugin handles the fireball spell, which damages every enemy hurt box in its area
+/// Fireball logic is only active during the State `GameState::Playing`
+impl Plugin for FireballPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            cast_fireball
+                .after(SpellCastSet)
+                .in_set(SimulationSet::Combat)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+const DAMAGE: f32 = 40.0;
+
+fn cast_fireball(
+    mut commands: Commands,
+    mut castspell_evr: EventReader<CastSpell>,
+    spatial_query: SpatialQuery,
+    collider_parents: Query<&ColliderParent>,
+    ally_castle: Res<AllyCastle>,
+    mut dealdamage_evw: EventWriter<DealDamage>,
+) {
+    for ev in castspell_evr.read() {
+        if ev.spell != Spell::Fireball {
+            continue;
+        }
+
+        /* Units, towers and castles can have several hurt boxes, damage each of them once */
+        let mut targets: Vec<Entity> = spatial_query
+            .shape_intersections(
+                &Collider::ball(ev.spell.radius()),
+                ev.position,
+                0.0,
+                SpatialQueryFilter::new().with_masks([SensorLayers::EnemyHurt]),
+            )
+            .into_iter()
+            .filter_map(|entity| Some(collider_parents.get(entity).ok()?.get()))
+            .collect();
+        targets.sort();
+        targets.dedup();
+
+        for entity in targets {
+            dealdamage_evw.send(DealDamage {
+                target: entity,
+                damage: Damage::new(DAMAGE, DamageType::Magic),
+                source: ally_castle.0.map(|castle| HealthDeltaSource {
+                    entity: castle,
+                    faction: Faction::Ally,
+                }),
+            });
+        }
+
+        commands.spawn((
+            spell_area_sprite(ev.spell, ev.position, Color::rgba(1.0, 0.4, 0.0, 0.6)),
+            SpellEffect(Timer::from_seconds(0.4, TimerMode::Once)),
+            MatchEntity,
+        ));
+    }
+}
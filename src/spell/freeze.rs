@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+
+use crate::{
+    command::SimulationSet,
+    common::{
+        status::{ApplyStatusEffect, StatusEffectDefinition, StatusKind},
+        Faction,
+    },
+    session::MatchEntity,
+    units::UnitKind,
+    GameState,
+};
+
+use super::{spell_area_sprite, CastSpell, Spell, SpellCastSet, SpellEffect};
+
+pub struct FreezePlugin;
+
+/// This plugin handles the freeze spell, which stuns enemy units in its area for a while
+/// Freeze logic is only active during the State `GameState::Playing`
+impl Plugin for FreezePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            cast_freeze
+                .after(SpellCastSet)
+                .in_set(SimulationSet::Combat)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Seconds the units are stunned
+const FREEZE_SECONDS: f32 = 3.0;
+
+fn cast_freeze(
+    mut commands: Commands,
+    mut castspell_evr: EventReader<CastSpell>,
+    units: Query<(Entity, &Faction, &GlobalTransform), With<UnitKind>>,
+    mut applystatuseffect_evw: EventWriter<ApplyStatusEffect>,
+) {
+    for ev in castspell_evr.read() {
+        if ev.spell != Spell::Freeze {
+            continue;
+        }
+
+        for (entity, faction, transform) in &units {
+            if *faction == Faction::Enemy
+                && transform.translation().truncate().distance(ev.position) <= ev.spell.radius()
+            {
+                applystatuseffect_evw.send(ApplyStatusEffect {
+                    entity,
+                    effect: StatusEffectDefinition {
+                        kind: StatusKind::Stun,
+                        magnitude: 1.0,
+                        duration: FREEZE_SECONDS,
+                    },
+                });
+            }
+        }
+
+        commands.spawn((
+            spell_area_sprite(ev.spell, ev.position, Color::rgba(0.5, 0.8, 1.0, 0.5)),
+            SpellEffect(Timer::from_seconds(0.4, TimerMode::Once)),
+            MatchEntity,
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::{
+    command::SimulationSet,
+    common::{attributes::ApplyHealthDelta, Faction},
+    session::MatchEntity,
+    units::UnitKind,
+    GameState,
+};
+
+use super::{spell_area_sprite, CastSpell, Spell, SpellCastSet};
+
+pub struct HealPlugin;
+
+/// This plugin handles the heal zone spell, which restores the health of allied units in its area over time
+/// Heal logic is only active during the State `GameState::Playing`
+impl Plugin for HealPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (cast_heal_zone.after(SpellCastSet), heal_zone)
+                .chain()
+                .in_set(SimulationSet::Combat)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct HealZone {
+    pub radius: f32,
+    /// Health per second
+    pub heal: f32,
+    pub lifetime: Timer,
+}
+
+impl HealZone {
+    const HEAL: f32 = 15.0;
+    const SECONDS: f32 = 5.0;
+}
+
+fn cast_heal_zone(mut commands: Commands, mut castspell_evr: EventReader<CastSpell>) {
+    for ev in castspell_evr.read() {
+        if ev.spell != Spell::HealZone {
+            continue;
+        }
+
+        commands.spawn((
+            spell_area_sprite(ev.spell, ev.position, Color::rgba(0.2, 0.9, 0.3, 0.3)),
+            HealZone {
+                radius: ev.spell.radius(),
+                heal: HealZone::HEAL,
+                lifetime: Timer::from_seconds(HealZone::SECONDS, TimerMode::Once),
+            },
+            MatchEntity,
+        ));
+    }
+}
+
+fn heal_zone(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut heal_zones: Query<(Entity, &mut HealZone, &GlobalTransform)>,
+    units: Query<(Entity, &Faction, &GlobalTransform), With<UnitKind>>,
+    mut applyhealthdelta_evw: EventWriter<ApplyHealthDelta>,
+) {
+    for (zone_entity, mut heal_zone, zone_transform) in &mut heal_zones {
+        let center = zone_transform.translation().truncate();
+        for (entity, faction, transform) in &units {
+            if *faction == Faction::Ally
+                && transform.translation().truncate().distance(center) <= heal_zone.radius
+            {
+                applyhealthdelta_evw.send(ApplyHealthDelta {
+                    entity,
+                    delta: heal_zone.heal * time.delta_seconds(),
+                    source: None,
+                });
+            }
+        }
+
+        if heal_zone.lifetime.tick(time.delta()).finished() {
+            commands.entity(zone_entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,174 @@
+pub mod fireball;
+pub mod freeze;
+pub mod heal;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    session::MatchSetupSet,
+    GameState,
+};
+
+use self::{fireball::FireballPlugin, freeze::FreezePlugin, heal::HealPlugin};
+
+pub struct SpellPlugin;
+
+/// This plugin handles the spells the player casts with mana
+/// Spell logic is only active during the State `GameState::Playing`
+impl Plugin for SpellPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((FireballPlugin, HealPlugin, FreezePlugin))
+            .init_resource::<Mana>()
+            .add_event::<CastSpell>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_mana.in_set(MatchSetupSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (regenerate_mana, process_spell_commands)
+                    .chain()
+                    .in_set(SpellCastSet)
+                    .in_set(SimulationSet::Commands)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                fade_spell_effects.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Spells handle their [`CastSpell`] events after this set
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct SpellCastSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Spell {
+    /// Damages every enemy in the area at once
+    Fireball,
+    /// Heals allied units in the area over time
+    HealZone,
+    /// Stops enemy units in the area from moving and attacking for a while
+    Freeze,
+}
+
+impl Spell {
+    pub const ALL: [Self; 3] = [Self::Fireball, Self::HealZone, Self::Freeze];
+
+    pub fn cost(&self) -> f32 {
+        match self {
+            Self::Fireball => 40.0,
+            Self::HealZone => 30.0,
+            Self::Freeze => 50.0,
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        match self {
+            Self::Fireball => 80.0,
+            Self::HealZone => 100.0,
+            Self::Freeze => 120.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fireball => "Fireball",
+            Self::HealZone => "Heal",
+            Self::Freeze => "Freeze",
+        }
+    }
+}
+
+/// Spent on spells, regenerates over time
+#[derive(Debug, Resource)]
+pub struct Mana {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Mana {
+    const MAX: f32 = 100.0;
+    /// Mana per second
+    const REGENERATION: f32 = 4.0;
+}
+
+impl Default for Mana {
+    fn default() -> Self {
+        Self {
+            current: Self::MAX / 2.0,
+            max: Self::MAX,
+        }
+    }
+}
+
+/// A paid for spell that takes effect at the position
+#[derive(Debug, Event)]
+pub struct CastSpell {
+    pub spell: Spell,
+    pub position: Vec2,
+}
+
+/// Short lived visual of a spell, fades out and is despawned once the timer finishes
+#[derive(Debug, Component)]
+pub struct SpellEffect(pub Timer);
+
+/// Translucent square covering the area of a spell
+pub fn spell_area_sprite(spell: Spell, position: Vec2, color: Color) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::splat(spell.radius() * 2.0)),
+            ..Default::default()
+        },
+        transform: Transform::from_translation(position.extend(0.5)),
+        ..Default::default()
+    }
+}
+
+fn reset_mana(mut mana: ResMut<Mana>) {
+    *mana = Mana::default();
+}
+
+fn regenerate_mana(time: Res<Time>, mut mana: ResMut<Mana>) {
+    mana.current = (mana.current + Mana::REGENERATION * time.delta_seconds()).min(mana.max);
+}
+
+fn process_spell_commands(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut mana: ResMut<Mana>,
+    mut castspell_evw: EventWriter<CastSpell>,
+) {
+    for ev in playercommand_evr.read() {
+        let PlayerAction::CastSpell { spell, x, y } = ev.action else {
+            continue;
+        };
+
+        if mana.current >= spell.cost() {
+            mana.current -= spell.cost();
+            castspell_evw.send(CastSpell {
+                spell,
+                position: Vec2::new(x as f32, y as f32),
+            });
+        }
+    }
+}
+
+fn fade_spell_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut effects: Query<(Entity, &mut SpellEffect, &mut Sprite)>,
+) {
+    for (entity, mut effect, mut sprite) in &mut effects {
+        if effect.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let alpha = sprite.color.a();
+        sprite.color.set_a(alpha.min(effect.0.percent_left()));
+    }
+}
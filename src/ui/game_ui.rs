@@ -1,17 +1,32 @@
 use crate::{
     castle::{
-        spawner::Wave, upgrade::SpawnCooldownReduction, AllyCastle, Castle, EnemyCastle, Gold,
-        QueueAllyUnit, SpawnQueue,
+        economy::{
+            Buildings, EconomyRegistry, GoldCategory, GoldTransaction, PurchaseRejected,
+            RejectionReason, TransactionKind,
+        },
+        spawner::Wave,
+        tech::{Research, TechRegistry, TechStatus},
+        tower::{Tower, TowerSlots},
+        upgrade::SpawnCooldownReduction,
+        AllyCastle, Castle, EnemyCastle, Gold, QueuedUnit, SpawnQueue,
     },
+    click::ClickDamageUpgrade,
+    command::{PendingPlayerActions, PlayerAction},
     common::{attributes::Health, Faction},
-    loading::UiAssets,
+    loading::{TextureAssets, UiAssets},
+    session::{MatchEntity, MatchSetupSet},
+    spell::{Mana, Spell},
     units::{
+        definition::UnitRegistry,
         upgrade::{AttackCooldownUpgrade, ShieldUpgrade},
         UnitKind,
     },
     GameState,
 };
-use bevy::prelude::*;
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+};
 
 pub struct GameUiPlugin;
 
@@ -19,52 +34,150 @@ pub struct GameUiPlugin;
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(GameState::Playing),
-            (setup_game_ui, setup_resource_ui, setup_health_ui),
-        )
-        .add_systems(
-            Update,
-            (
-                click_spawn_button,
-                update_spawn_button_text,
-                click_spawn_cooldown_reduction_button,
-                update_spawn_cooldown_reduction_button,
-                update_gold_ui,
-                update_wave_ui,
-                update_castle_health_ui,
-                update_soldier_shield_button,
-                update_soldier_attackspeed_button,
-                click_soldier_shield_button,
-                click_soldier_attackspeed_button,
+        app.init_resource::<ArmedSpell>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    setup_game_ui,
+                    setup_resource_ui,
+                    setup_health_ui,
+                    setup_spell_bar,
+                    setup_tech_tree_panel,
+                    setup_spawn_queue_strip,
+                )
+                    .in_set(MatchSetupSet),
             )
-                .run_if(in_state(GameState::Playing)),
-        );
+            .add_systems(
+                Update,
+                (
+                    click_spawn_button,
+                    update_spawn_button_text,
+                    click_spawn_cooldown_reduction_button,
+                    update_spawn_cooldown_reduction_button,
+                    update_gold_ui,
+                    update_wave_ui,
+                    update_castle_health_ui,
+                    update_soldier_shield_button,
+                    update_soldier_attackspeed_button,
+                    click_soldier_shield_button,
+                    click_soldier_attackspeed_button,
+                    click_tower_button,
+                    update_tower_button,
+                    click_click_damage_button,
+                    update_click_damage_button,
+                    click_pause_button,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (click_spell_button, arm_spell_hotkeys, update_spell_bar)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_tech_tree_panel,
+                    click_tech_node_button,
+                    update_tech_tree_panel,
+                    update_spawn_button_lock,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    click_building_button,
+                    update_building_button,
+                    spawn_floating_gold,
+                    spawn_rejection_feedback,
+                    animate_floating_text,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    click_queue_icon,
+                    update_spawn_queue_strip,
+                    update_spawn_queue_progress,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
 #[derive(Debug, Component)]
 struct SpawnButton(pub UnitKind);
 
-#[derive(Debug, Default, Component)]
-struct SpawnButtonText;
+#[derive(Debug, Component)]
+struct SpawnButtonText(UnitKind);
 
-fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
+fn setup_game_ui(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    textures: Res<TextureAssets>,
+    unit_registry: UnitRegistry,
+    economy_registry: EconomyRegistry,
+) {
     info!("game_ui");
     commands
-        .spawn((NodeBundle {
-            style: Style {
-                width: Val::Percent(20.0),
-                height: Val::Percent(100.0),
-                left: Val::Percent(1.0),
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::FlexStart,
-                justify_content: JustifyContent::SpaceEvenly,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(20.0),
+                    height: Val::Percent(100.0),
+                    left: Val::Percent(1.0),
+                    flex_direction: FlexDirection::Column,
+                    flex_wrap: FlexWrap::Wrap,
+                    align_items: AlignItems::FlexStart,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        },)) /* TODO: Another NodeBundle for FlexDirection Row */
+            MatchEntity,
+        )) /* TODO: Another NodeBundle for FlexDirection Row */
         .with_children(|children| {
+            for kind in UnitKind::ALL {
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(128.0),
+                                height: Val::Px(128.0),
+                                flex_direction: FlexDirection::Row,
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            image: ui_assets.soldier_button.clone().into(),
+                            ..Default::default()
+                        },
+                        SpawnButton(kind),
+                    ))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(TextBundle::from_section(
+                                format!("{}", 0),
+                                TextStyle {
+                                    font_size: 30.0,
+                                    color: Color::rgb(0.0, 0.0, 0.0),
+                                    ..default()
+                                },
+                            ))
+                            .insert(SpawnButtonText(kind));
+                        parent.spawn(TextBundle::from_section(
+                            format!("{}", unit_registry.get(&kind).cost),
+                            TextStyle {
+                                font_size: 30.0,
+                                color: Color::rgb(0.9, 0.9, 0.0),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+
             children
                 .spawn((
                     ButtonBundle {
@@ -76,10 +189,10 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                             align_items: AlignItems::FlexStart,
                             ..Default::default()
                         },
-                        image: ui_assets.soldier_button.clone().into(),
+                        image: ui_assets.tech_castle_button.clone().into(),
                         ..Default::default()
                     },
-                    SpawnButton(UnitKind::Soldier),
+                    SpawnCooldownReductionButton,
                 ))
                 .with_children(|parent| {
                     parent
@@ -91,15 +204,17 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                                 ..default()
                             },
                         ))
-                        .insert(SpawnButtonText);
-                    parent.spawn(TextBundle::from_section(
-                        format!("{}", UnitKind::Soldier.cost()),
-                        TextStyle {
-                            font_size: 30.0,
-                            color: Color::rgb(0.9, 0.9, 0.0),
-                            ..default()
-                        },
-                    ));
+                        .insert(SpawnCooldownReductionButtonLevelText);
+                    parent
+                        .spawn(TextBundle::from_section(
+                            format!("{}", 0),
+                            TextStyle {
+                                font_size: 30.0,
+                                color: Color::rgb(0.9, 0.9, 0.0),
+                                ..default()
+                            },
+                        ))
+                        .insert(SpawnCooldownReductionButtonCostText);
                 });
 
             children
@@ -113,32 +228,34 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                             align_items: AlignItems::FlexStart,
                             ..Default::default()
                         },
-                        image: ui_assets.tech_castle_button.clone().into(),
+                        image: ui_assets.soldier_shield.clone().into(),
                         ..Default::default()
                     },
-                    SpawnCooldownReductionButton,
+                    SoldierShieldButton,
                 ))
                 .with_children(|parent| {
-                    parent
-                        .spawn(TextBundle::from_section(
+                    parent.spawn((
+                        TextBundle::from_section(
                             format!("{}", 0),
                             TextStyle {
                                 font_size: 30.0,
                                 color: Color::rgb(0.0, 0.0, 0.0),
                                 ..default()
                             },
-                        ))
-                        .insert(SpawnCooldownReductionButtonLevelText);
-                    parent
-                        .spawn(TextBundle::from_section(
+                        ),
+                        SoldierShieldButtonLevelText,
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(
                             format!("{}", 0),
                             TextStyle {
                                 font_size: 30.0,
                                 color: Color::rgb(0.9, 0.9, 0.0),
                                 ..default()
                             },
-                        ))
-                        .insert(SpawnCooldownReductionButtonCostText);
+                        ),
+                        SoldierShieldButtonCostText,
+                    ));
                 });
 
             children
@@ -152,10 +269,10 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                             align_items: AlignItems::FlexStart,
                             ..Default::default()
                         },
-                        image: ui_assets.soldier_shield.clone().into(),
+                        image: ui_assets.soldier_attackspeed.clone().into(),
                         ..Default::default()
                     },
-                    SoldierShieldButton,
+                    SoldierAttackspeedButton,
                 ))
                 .with_children(|parent| {
                     parent.spawn((
@@ -167,7 +284,7 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                                 ..default()
                             },
                         ),
-                        SoldierShieldButtonLevelText,
+                        SoldierAttackspeedButtonLevelText,
                     ));
                     parent.spawn((
                         TextBundle::from_section(
@@ -178,7 +295,7 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                                 ..default()
                             },
                         ),
-                        SoldierShieldButtonCostText,
+                        SoldierAttackspeedButtonCostText,
                     ));
                 });
 
@@ -193,10 +310,10 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                             align_items: AlignItems::FlexStart,
                             ..Default::default()
                         },
-                        image: ui_assets.soldier_attackspeed.clone().into(),
+                        image: textures.castle.clone().into(),
                         ..Default::default()
                     },
-                    SoldierAttackspeedButton,
+                    TowerButton,
                 ))
                 .with_children(|parent| {
                     parent.spawn((
@@ -208,7 +325,45 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                                 ..default()
                             },
                         ),
-                        SoldierAttackspeedButtonLevelText,
+                        TowerButtonCountText,
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        format!("{}", Tower::COST),
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.0),
+                            ..default()
+                        },
+                    ));
+                });
+
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(128.0),
+                            height: Val::Px(128.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::FlexStart,
+                            ..Default::default()
+                        },
+                        background_color: Color::rgb(0.6, 0.15, 0.15).into(),
+                        ..Default::default()
+                    },
+                    ClickDamageButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("{}", 0),
+                            TextStyle {
+                                font_size: 30.0,
+                                color: Color::rgb(0.0, 0.0, 0.0),
+                                ..default()
+                            },
+                        ),
+                        ClickDamageButtonLevelText,
                     ));
                     parent.spawn((
                         TextBundle::from_section(
@@ -219,9 +374,52 @@ fn setup_game_ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                                 ..default()
                             },
                         ),
-                        SoldierAttackspeedButtonCostText,
+                        ClickDamageButtonCostText,
                     ));
                 });
+
+            for (index, building) in economy_registry.get().buildings().iter().enumerate() {
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(128.0),
+                                height: Val::Px(128.0),
+                                flex_direction: FlexDirection::Row,
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            background_color: Color::rgb(0.3, 0.45, 0.15).into(),
+                            ..Default::default()
+                        },
+                        BuildingButton(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                format!("{}\n{}", building.name, 0),
+                                TextStyle {
+                                    font_size: 30.0,
+                                    color: Color::rgb(0.0, 0.0, 0.0),
+                                    ..default()
+                                },
+                            ),
+                            BuildingButtonCountText(index),
+                        ));
+                        parent.spawn((
+                            TextBundle::from_section(
+                                format!("{}", building.cost),
+                                TextStyle {
+                                    font_size: 30.0,
+                                    color: Color::rgb(0.9, 0.9, 0.0),
+                                    ..default()
+                                },
+                            ),
+                            BuildingButtonCostText(index),
+                        ));
+                    });
+            }
         });
 }
 
@@ -230,14 +428,12 @@ fn click_spawn_button(
         (&Interaction, &SpawnButton),
         (Changed<Interaction>, With<Button>),
     >,
-    mut queueallyunit_evw: EventWriter<QueueAllyUnit>,
+    mut player_actions: ResMut<PendingPlayerActions>,
 ) {
     for (interaction, spawn_button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                queueallyunit_evw.send(QueueAllyUnit {
-                    kind: spawn_button.0,
-                });
+                player_actions.send(PlayerAction::QueueUnit(spawn_button.0));
             }
             Interaction::Hovered => { /* TODO; Color shaded */ }
             Interaction::None => { /* TODO; Color normal */ }
@@ -246,17 +442,16 @@ fn click_spawn_button(
 }
 
 fn update_spawn_button_text(
-    mut query: Query<&mut Text, With<SpawnButtonText>>,
+    mut query: Query<(&mut Text, &SpawnButtonText)>,
     ally_castle: Res<AllyCastle>,
     spawn_queues: Query<&SpawnQueue, With<Castle>>,
 ) {
     if let Some(entity) = ally_castle.0 {
         if let Ok(spawn_queue) = spawn_queues.get(entity) {
-            for mut text in &mut query {
+            for (mut text, spawn_button_text) in &mut query {
                 let value = spawn_queue
-                    .units
-                    .iter()
-                    .filter(|x| matches!(x, UnitKind::Soldier))
+                    .kinds()
+                    .filter(|kind| *kind == spawn_button_text.0)
                     .count();
                 text.sections[0].value = format!("{}", value);
             }
@@ -269,23 +464,12 @@ fn click_spawn_cooldown_reduction_button(
         (&Interaction, &SpawnCooldownReductionButton),
         (Changed<Interaction>, With<Button>),
     >,
-    mut spawn_cooldown_reduction: Query<&mut SpawnCooldownReduction>,
-    ally_castle: Res<AllyCastle>,
-    mut gold: ResMut<Gold>,
+    mut player_actions: ResMut<PendingPlayerActions>,
 ) {
     for (interaction, _spawn_button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                if let Some(entity) = ally_castle.0 {
-                    if let Ok(mut spawn_cooldown_reduction) =
-                        spawn_cooldown_reduction.get_mut(entity)
-                    {
-                        let cost = spawn_cooldown_reduction.cost();
-                        if gold.0 >= cost && spawn_cooldown_reduction.level_up() {
-                            gold.0 -= cost;
-                        }
-                    }
-                }
+                player_actions.send(PlayerAction::UpgradeSpawnCooldownReduction);
             }
             Interaction::Hovered => { /* TODO; Color shaded */ }
             Interaction::None => { /* TODO; Color normal */ }
@@ -336,18 +520,21 @@ struct SpawnCooldownReductionButtonCostText;
 /* Gold, Wave */
 fn setup_resource_ui(mut commands: Commands, gold: Res<Gold>, wave: Res<Wave>) {
     commands
-        .spawn((NodeBundle {
-            style: Style {
-                width: Val::Percent(98.0),
-                height: Val::Percent(5.0),
-                left: Val::Percent(1.0),
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::SpaceBetween,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(98.0),
+                    height: Val::Percent(5.0),
+                    left: Val::Percent(1.0),
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        },))
+            MatchEntity,
+        ))
         .with_children(|children| {
             children.spawn((
                 TextBundle::from_section(
@@ -371,25 +558,89 @@ fn setup_resource_ui(mut commands: Commands, gold: Res<Gold>, wave: Res<Wave>) {
                 ),
                 WaveUi,
             ));
+            children.spawn((
+                TextBundle::from_section(
+                    format!("Next: {}", wave.current),
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::rgb(0.0, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                WavePreviewUi,
+            ));
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(120.0),
+                            height: Val::Px(40.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                        ..Default::default()
+                    },
+                    TechTreeButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Tech",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(120.0),
+                            height: Val::Px(40.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                        ..Default::default()
+                    },
+                    PauseButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Pause",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
         });
 }
 
 /* Castle Health */
 fn setup_health_ui(mut commands: Commands) {
     commands
-        .spawn((NodeBundle {
-            style: Style {
-                width: Val::Percent(98.0),
-                height: Val::Percent(5.0),
-                left: Val::Percent(1.0),
-                top: Val::Percent(95.0),
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::SpaceBetween,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(98.0),
+                    height: Val::Percent(5.0),
+                    left: Val::Percent(1.0),
+                    top: Val::Percent(95.0),
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        },))
+            MatchEntity,
+        ))
         .with_children(|children| {
             children.spawn((
                 TextBundle::from_section(
@@ -422,6 +673,23 @@ struct GoldUi;
 #[derive(Debug, Default, Component)]
 struct WaveUi;
 
+#[derive(Debug, Default, Component)]
+struct WavePreviewUi;
+
+#[derive(Debug, Component)]
+struct PauseButton;
+
+fn click_pause_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<PauseButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Paused);
+        }
+    }
+}
+
 #[derive(Debug, Component)]
 struct CastleHealthUi(Faction);
 
@@ -431,10 +699,22 @@ fn update_gold_ui(mut gold_uis: Query<&mut Text, With<GoldUi>>, gold: Res<Gold>)
     }
 }
 
-fn update_wave_ui(mut wave_uis: Query<&mut Text, With<WaveUi>>, wave: Res<Wave>) {
+fn update_wave_ui(
+    mut wave_uis: Query<&mut Text, (With<WaveUi>, Without<WavePreviewUi>)>,
+    mut wave_preview_uis: Query<&mut Text, (With<WavePreviewUi>, Without<WaveUi>)>,
+    wave: Res<Wave>,
+) {
     for mut text in &mut wave_uis {
         text.sections[0].value = format!("Wave:{}", wave.level);
     }
+
+    for mut text in &mut wave_preview_uis {
+        text.sections[0].value = format!(
+            "Next: {} in {:.0}s",
+            wave.current,
+            wave.timer.remaining_secs().ceil()
+        );
+    }
 }
 
 fn update_castle_health_ui(
@@ -469,21 +749,12 @@ fn click_soldier_shield_button(
         (&Interaction, &SoldierShieldButton),
         (Changed<Interaction>, With<Button>),
     >,
-    mut soldier_shields: Query<&mut ShieldUpgrade, With<Castle>>,
-    ally_castle: Res<AllyCastle>,
-    mut gold: ResMut<Gold>,
+    mut player_actions: ResMut<PendingPlayerActions>,
 ) {
     for (interaction, _spawn_button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                if let Some(entity) = ally_castle.0 {
-                    if let Ok(mut soldier_shield) = soldier_shields.get_mut(entity) {
-                        let cost = soldier_shield.cost();
-                        if gold.0 >= cost && soldier_shield.level_up() {
-                            gold.0 -= cost;
-                        }
-                    }
-                }
+                player_actions.send(PlayerAction::UpgradeShield);
             }
             Interaction::Hovered => { /* TODO; Color shaded */ }
             Interaction::None => { /* TODO; Color normal */ }
@@ -536,21 +807,12 @@ fn click_soldier_attackspeed_button(
         (&Interaction, &SoldierAttackspeedButton),
         (Changed<Interaction>, With<Button>),
     >,
-    mut soldier_attackspeeds: Query<&mut AttackCooldownUpgrade, With<Castle>>,
-    ally_castle: Res<AllyCastle>,
-    mut gold: ResMut<Gold>,
+    mut player_actions: ResMut<PendingPlayerActions>,
 ) {
     for (interaction, _spawn_button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                if let Some(entity) = ally_castle.0 {
-                    if let Ok(mut soldier_attackspeed) = soldier_attackspeeds.get_mut(entity) {
-                        let cost = soldier_attackspeed.cost();
-                        if gold.0 >= cost && soldier_attackspeed.level_up() {
-                            gold.0 -= cost;
-                        }
-                    }
-                }
+                player_actions.send(PlayerAction::UpgradeAttackCooldown);
             }
             Interaction::Hovered => { /* TODO; Color shaded */ }
             Interaction::None => { /* TODO; Color normal */ }
@@ -588,3 +850,701 @@ fn update_soldier_attackspeed_button(
         }
     }
 }
+
+#[derive(Debug, Component)]
+struct TowerButton;
+
+#[derive(Debug, Default, Component)]
+struct TowerButtonCountText;
+
+fn click_tower_button(
+    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<TowerButton>)>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+) {
+    for interaction in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                player_actions.send(PlayerAction::BuildTower);
+            }
+            Interaction::Hovered => { /* TODO; Color shaded */ }
+            Interaction::None => { /* TODO; Color normal */ }
+        }
+    }
+}
+
+fn update_tower_button(
+    mut counttext: Query<&mut Text, With<TowerButtonCountText>>,
+    tower_slots: Query<&TowerSlots>,
+    towers: Query<(), With<Tower>>,
+    ally_castle: Res<AllyCastle>,
+) {
+    if let Some(entity) = ally_castle.0 {
+        if let Ok(tower_slots) = tower_slots.get(entity) {
+            for mut text in &mut counttext {
+                text.sections[0].value = format!("{}", tower_slots.count(&towers));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+struct ClickDamageButton;
+
+#[derive(Debug, Default, Component)]
+struct ClickDamageButtonLevelText;
+
+#[derive(Debug, Default, Component)]
+struct ClickDamageButtonCostText;
+
+fn click_click_damage_button(
+    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<ClickDamageButton>)>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+) {
+    for interaction in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                player_actions.send(PlayerAction::UpgradeClickDamage);
+            }
+            Interaction::Hovered => { /* TODO; Color shaded */ }
+            Interaction::None => { /* TODO; Color normal */ }
+        }
+    }
+}
+
+fn update_click_damage_button(
+    mut leveltext: Query<
+        &mut Text,
+        (
+            With<ClickDamageButtonLevelText>,
+            Without<ClickDamageButtonCostText>,
+        ),
+    >,
+    mut costtext: Query<
+        &mut Text,
+        (
+            With<ClickDamageButtonCostText>,
+            Without<ClickDamageButtonLevelText>,
+        ),
+    >,
+    click_damages: Query<&ClickDamageUpgrade, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+) {
+    if let Some(entity) = ally_castle.0 {
+        if let Ok(click_damage) = click_damages.get(entity) {
+            for mut text in &mut leveltext {
+                text.sections[0].value = format!("{}", click_damage.level());
+            }
+
+            for mut text in &mut costtext {
+                text.sections[0].value = format!("{}", click_damage.cost());
+            }
+        }
+    }
+}
+
+/// Spell that is cast at the next left click on the battlefield
+#[derive(Debug, Default, Resource)]
+pub struct ArmedSpell(pub Option<Spell>);
+
+#[derive(Debug, Component)]
+struct SpellButton(Spell);
+
+#[derive(Debug, Default, Component)]
+struct ManaUi;
+
+/* Spells, Mana */
+fn setup_spell_bar(mut commands: Commands, mut armed_spell: ResMut<ArmedSpell>) {
+    armed_spell.0 = None;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(8.0),
+                    top: Val::Percent(86.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            MatchEntity,
+        ))
+        .with_children(|children| {
+            children.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::rgb(0.3, 0.5, 1.0),
+                        ..default()
+                    },
+                ),
+                ManaUi,
+            ));
+            for (index, spell) in Spell::ALL.into_iter().enumerate() {
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(180.0),
+                                height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                            ..Default::default()
+                        },
+                        SpellButton(spell),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{} {} ({:.0})", index + 1, spell.name(), spell.cost()),
+                            TextStyle {
+                                font_size: 25.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+/// Pressing the button of the armed spell again disarms it
+fn toggle_armed_spell(armed_spell: &mut ArmedSpell, spell: Spell) {
+    armed_spell.0 = match armed_spell.0 {
+        Some(armed) if armed == spell => None,
+        _ => Some(spell),
+    };
+}
+
+fn click_spell_button(
+    interaction_query: Query<(&Interaction, &SpellButton), Changed<Interaction>>,
+    mut armed_spell: ResMut<ArmedSpell>,
+) {
+    for (interaction, spell_button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            toggle_armed_spell(&mut armed_spell, spell_button.0);
+        }
+    }
+}
+
+fn arm_spell_hotkeys(
+    mut keyboard_evr: EventReader<KeyboardInput>,
+    mut armed_spell: ResMut<ArmedSpell>,
+) {
+    for ev in keyboard_evr.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+        let spell = match ev.key_code {
+            Some(KeyCode::Key1) => Spell::Fireball,
+            Some(KeyCode::Key2) => Spell::HealZone,
+            Some(KeyCode::Key3) => Spell::Freeze,
+            _ => continue,
+        };
+        toggle_armed_spell(&mut armed_spell, spell);
+    }
+}
+
+fn update_spell_bar(
+    mut mana_uis: Query<&mut Text, With<ManaUi>>,
+    mut spell_buttons: Query<(&mut BackgroundColor, &SpellButton)>,
+    mana: Res<Mana>,
+    armed_spell: Res<ArmedSpell>,
+) {
+    for mut text in &mut mana_uis {
+        text.sections[0].value = format!("Mana {:.0}/{:.0}", mana.current.floor(), mana.max);
+    }
+
+    for (mut color, spell_button) in &mut spell_buttons {
+        *color = if armed_spell.0 == Some(spell_button.0) {
+            Color::rgb(0.3, 0.3, 0.6)
+        } else if mana.current < spell_button.0.cost() {
+            Color::rgb(0.3, 0.1, 0.1)
+        } else {
+            Color::rgb(0.15, 0.15, 0.15)
+        }
+        .into();
+    }
+}
+
+/* Tech Tree */
+#[derive(Debug, Component)]
+struct TechTreeButton;
+
+#[derive(Debug, Component)]
+struct TechTreePanel;
+
+#[derive(Debug, Component)]
+struct TechNodeButton(usize);
+
+fn setup_tech_tree_panel(mut commands: Commands, tech_registry: TechRegistry) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    right: Val::Percent(1.0),
+                    top: Val::Percent(8.0),
+                    width: Val::Px(300.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+            TechTreePanel,
+            MatchEntity,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "Tech Tree (T)",
+                TextStyle {
+                    font_size: 30.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+            for (index, node) in tech_registry.get().nodes().iter().enumerate() {
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                height: Val::Px(40.0),
+                                padding: UiRect::horizontal(Val::Px(8.0)),
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: Color::rgb(0.1, 0.1, 0.1).into(),
+                            ..Default::default()
+                        },
+                        TechNodeButton(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            node.name.clone(),
+                            TextStyle {
+                                font_size: 25.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                        parent.spawn(TextBundle::from_section(
+                            format!("{}", node.cost),
+                            TextStyle {
+                                font_size: 25.0,
+                                color: Color::rgb(0.9, 0.9, 0.0),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn toggle_tech_tree_panel(
+    mut keyboard_evr: EventReader<KeyboardInput>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<TechTreeButton>)>,
+    mut panels: Query<&mut Style, With<TechTreePanel>>,
+) {
+    let pressed_key = keyboard_evr
+        .read()
+        .filter(|ev| ev.state == ButtonState::Pressed && ev.key_code == Some(KeyCode::T))
+        .count();
+    let pressed_button = interaction_query
+        .iter()
+        .filter(|interaction| **interaction == Interaction::Pressed)
+        .count();
+
+    if (pressed_key + pressed_button) % 2 == 1 {
+        for mut style in &mut panels {
+            style.display = match style.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+}
+
+fn click_tech_node_button(
+    interaction_query: Query<(&Interaction, &TechNodeButton), Changed<Interaction>>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+) {
+    for (interaction, tech_node_button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            player_actions.send(PlayerAction::Research(tech_node_button.0));
+        }
+    }
+}
+
+/// Locked nodes are dark, available ones blue or red if the gold is missing, researched ones green
+fn update_tech_tree_panel(
+    mut tech_node_buttons: Query<(&mut BackgroundColor, &TechNodeButton)>,
+    ally_castle: Res<AllyCastle>,
+    researches: Query<&Research, With<Castle>>,
+    gold: Res<Gold>,
+    tech_registry: TechRegistry,
+) {
+    let Some(research) = ally_castle.0.and_then(|entity| researches.get(entity).ok()) else {
+        return;
+    };
+    let tech_tree = tech_registry.get();
+
+    for (mut color, tech_node_button) in &mut tech_node_buttons {
+        let index = tech_node_button.0;
+        *color = match tech_tree.status(index, research) {
+            TechStatus::Locked => Color::rgb(0.1, 0.1, 0.1),
+            TechStatus::Available if gold.0 < tech_tree.nodes()[index].cost => {
+                Color::rgb(0.3, 0.1, 0.1)
+            }
+            TechStatus::Available => Color::rgb(0.15, 0.25, 0.5),
+            TechStatus::Researched => Color::rgb(0.15, 0.45, 0.15),
+        }
+        .into();
+    }
+}
+
+/// Units that still have to be researched are greyed out
+fn update_spawn_button_lock(
+    mut spawn_buttons: Query<(&mut BackgroundColor, &SpawnButton)>,
+    ally_castle: Res<AllyCastle>,
+    researches: Query<&Research, With<Castle>>,
+    tech_registry: TechRegistry,
+) {
+    let Some(research) = ally_castle.0.and_then(|entity| researches.get(entity).ok()) else {
+        return;
+    };
+
+    for (mut color, spawn_button) in &mut spawn_buttons {
+        *color = if tech_registry
+            .get()
+            .is_unit_unlocked(spawn_button.0, research)
+        {
+            Color::WHITE
+        } else {
+            Color::rgb(0.3, 0.3, 0.3)
+        }
+        .into();
+    }
+}
+
+/* Economy */
+#[derive(Debug, Component)]
+struct BuildingButton(usize);
+
+#[derive(Debug, Component)]
+struct BuildingButtonCountText(usize);
+
+#[derive(Debug, Component)]
+struct BuildingButtonCostText(usize);
+
+fn click_building_button(
+    interaction_query: Query<(&Interaction, &BuildingButton), Changed<Interaction>>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+) {
+    for (interaction, building_button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            player_actions.send(PlayerAction::Build(building_button.0));
+        }
+    }
+}
+
+/// Shows the count and the cost of the next building, "Max" once no more can be built
+fn update_building_button(
+    mut counttext: Query<(&mut Text, &BuildingButtonCountText), Without<BuildingButtonCostText>>,
+    mut costtext: Query<(&mut Text, &BuildingButtonCostText), Without<BuildingButtonCountText>>,
+    buildings: Query<&Buildings, With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    economy_registry: EconomyRegistry,
+) {
+    let Some(buildings) = ally_castle.0.and_then(|entity| buildings.get(entity).ok()) else {
+        return;
+    };
+    let definitions = economy_registry.get().buildings();
+
+    for (mut text, count_text) in &mut counttext {
+        text.sections[0].value = format!(
+            "{}\n{}",
+            definitions[count_text.0].name,
+            buildings.count(count_text.0)
+        );
+    }
+
+    for (mut text, cost_text) in &mut costtext {
+        let definition = &definitions[cost_text.0];
+        let built = buildings.count(cost_text.0);
+        text.sections[0].value = if built < definition.max {
+            format!("{}", definition.cost(built))
+        } else {
+            "Max".to_string()
+        };
+    }
+}
+
+/// Rises and fades out, like the gold that was earned or the reason a purchase was rejected
+#[derive(Debug, Component)]
+struct FloatingText(Timer);
+
+/// Spawned in the world at `position`, otherwise below the gold counter with the given offset from the left
+fn spawn_floating_text(
+    commands: &mut Commands,
+    text: String,
+    color: Color,
+    position: Option<Vec2>,
+    left: Val,
+) {
+    let style = TextStyle {
+        font_size: 30.0,
+        color,
+        ..default()
+    };
+    let floating_text = FloatingText(Timer::from_seconds(1.0, TimerMode::Once));
+
+    match position {
+        Some(position) => {
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(text, style),
+                    transform: Transform::from_translation(position.extend(10.0)),
+                    ..default()
+                },
+                floating_text,
+                MatchEntity,
+            ));
+        }
+        None => {
+            commands.spawn((
+                TextBundle::from_section(text, style).with_style(Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(6.0),
+                    left,
+                    ..default()
+                }),
+                floating_text,
+                MatchEntity,
+            ));
+        }
+    }
+}
+
+/// Gold earned on the battlefield floats up there, all other gold next to the gold counter
+fn spawn_floating_gold(
+    mut commands: Commands,
+    mut goldtransaction_evr: EventReader<GoldTransaction>,
+) {
+    for ev in goldtransaction_evr.read() {
+        if ev.kind == TransactionKind::Spent {
+            continue;
+        }
+
+        let (color, left) = match ev.category {
+            GoldCategory::Interest => (Color::rgb(0.4, 0.9, 0.3), Val::Percent(6.0)),
+            _ => (Color::rgb(0.9, 0.9, 0.0), Val::Percent(1.5)),
+        };
+        spawn_floating_text(
+            &mut commands,
+            format!("+{}", ev.amount),
+            color,
+            ev.position,
+            left,
+        );
+    }
+}
+
+fn spawn_rejection_feedback(
+    mut commands: Commands,
+    mut purchaserejected_evr: EventReader<PurchaseRejected>,
+) {
+    for ev in purchaserejected_evr.read() {
+        let text = match ev.reason {
+            RejectionReason::NotEnoughGold(missing) => format!("{} gold missing", missing),
+            RejectionReason::MaxLevel => "Maxed out".to_string(),
+            RejectionReason::Locked => "Research first".to_string(),
+            RejectionReason::NoSpace => "No space left".to_string(),
+            RejectionReason::AlreadySpawned => "Already spawned".to_string(),
+        };
+        spawn_floating_text(
+            &mut commands,
+            text,
+            Color::rgb(0.9, 0.2, 0.2),
+            None,
+            Val::Percent(10.0),
+        );
+    }
+}
+
+fn animate_floating_text(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut floating_texts: Query<(
+        Entity,
+        &mut FloatingText,
+        &mut Text,
+        &mut Transform,
+        Option<&mut Style>,
+    )>,
+) {
+    for (entity, mut floating_text, mut text, mut transform, style) in &mut floating_texts {
+        if floating_text.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        /* Ui nodes are placed by the layout, so they rise through their style instead */
+        match style {
+            Some(mut style) => style.top = Val::Percent(6.0 - 3.0 * floating_text.0.percent()),
+            None => transform.translation.y += 40.0 * time.delta_seconds(),
+        }
+        for section in &mut text.sections {
+            section.style.color.set_a(floating_text.0.percent_left());
+        }
+    }
+}
+
+/* Spawn Queue */
+/// One icon per unit in the spawn queue of the player, in the order they spawn
+#[derive(Debug, Default, Component)]
+struct SpawnQueueStrip {
+    /// Units the current icons stand for
+    shown: Vec<QueuedUnit>,
+}
+
+/// Cancels the queued unit with this id when clicked
+#[derive(Debug, Component)]
+struct QueueIcon(u32);
+
+/// Fills up while the first unit of the queue is produced
+#[derive(Debug, Component)]
+struct QueueProgressBar;
+
+fn setup_spawn_queue_strip(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(8.0),
+                left: Val::Percent(22.0),
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(2.0),
+                ..default()
+            },
+            ..default()
+        },
+        SpawnQueueStrip::default(),
+        MatchEntity,
+    ));
+}
+
+/// Respawns the icons whenever units were queued, cancelled or spawned
+fn update_spawn_queue_strip(
+    mut commands: Commands,
+    mut strips: Query<(Entity, &mut SpawnQueueStrip)>,
+    ally_castle: Res<AllyCastle>,
+    spawn_queues: Query<&SpawnQueue, With<Castle>>,
+    unit_registry: UnitRegistry,
+) {
+    let Some(spawn_queue) = ally_castle
+        .0
+        .and_then(|entity| spawn_queues.get(entity).ok())
+    else {
+        return;
+    };
+
+    for (entity, mut strip) in &mut strips {
+        if spawn_queue.iter().eq(strip.shown.iter()) {
+            continue;
+        }
+        strip.shown = spawn_queue.iter().copied().collect();
+
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|parent| {
+                for (index, QueuedUnit { id, kind }) in strip.shown.iter().copied().enumerate() {
+                    let texture = unit_registry.get(&kind).textures.get(&Faction::Ally);
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(28.0),
+                                    height: Val::Px(28.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                image: texture.into(),
+                                ..Default::default()
+                            },
+                            QueueIcon(id),
+                        ))
+                        .with_children(|parent| {
+                            /* All units share a texture, so the initial tells them apart */
+                            parent.spawn(TextBundle::from_section(
+                                format!("{:?}", kind)[..1].to_string(),
+                                TextStyle {
+                                    font_size: 18.0,
+                                    color: Color::rgb(0.0, 0.0, 0.0),
+                                    ..default()
+                                },
+                            ));
+                            if index == 0 {
+                                parent.spawn((
+                                    NodeBundle {
+                                        style: Style {
+                                            position_type: PositionType::Absolute,
+                                            bottom: Val::Px(0.0),
+                                            left: Val::Px(0.0),
+                                            width: Val::Percent(0.0),
+                                            height: Val::Px(4.0),
+                                            ..default()
+                                        },
+                                        background_color: Color::rgb(0.2, 0.8, 0.2).into(),
+                                        ..default()
+                                    },
+                                    QueueProgressBar,
+                                ));
+                            }
+                        });
+                }
+            });
+    }
+}
+
+fn update_spawn_queue_progress(
+    mut progress_bars: Query<&mut Style, With<QueueProgressBar>>,
+    ally_castle: Res<AllyCastle>,
+    spawn_queues: Query<&SpawnQueue, With<Castle>>,
+) {
+    let Some(spawn_queue) = ally_castle
+        .0
+        .and_then(|entity| spawn_queues.get(entity).ok())
+    else {
+        return;
+    };
+
+    for mut style in &mut progress_bars {
+        style.width = Val::Percent(100.0 * spawn_queue.timer.percent());
+    }
+}
+
+fn click_queue_icon(
+    interaction_query: Query<(&Interaction, &QueueIcon), Changed<Interaction>>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+) {
+    for (interaction, queue_icon) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            player_actions.send(PlayerAction::CancelQueuedUnit(queue_icon.0));
+        }
+    }
+}
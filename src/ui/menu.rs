@@ -1,5 +1,11 @@
+use crate::castle::economy::{GoldCategory, GoldLedger};
 use crate::castle::spawner::Wave;
+use crate::command::replay::Replay;
+use crate::common::Faction;
 use crate::loading::UiAssets;
+use crate::save::{self, RestoreMatch};
+use crate::session::{Difficulty, MatchSettings, RestartMatch};
+use crate::units::death::KillStatistics;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_xpbd_2d::components::LinearVelocity;
@@ -13,21 +19,37 @@ impl Plugin for MenuPlugin {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
             .add_systems(
                 Update,
-                click_menu_button
-                    .run_if(in_state(GameState::Menu).or_else(in_state(GameState::Instructions))),
+                click_menu_button.run_if(
+                    in_state(GameState::Menu)
+                        .or_else(in_state(GameState::Instructions))
+                        .or_else(in_state(GameState::GameOver))
+                        .or_else(in_state(GameState::Won)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    click_difficulty_button,
+                    click_adjust_setting_button,
+                    update_difficulty_menu.run_if(resource_changed::<MatchSettings>()),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
             )
             .add_systems(OnExit(GameState::Menu), cleanup_menu)
             .add_systems(OnEnter(GameState::Instructions), setup_instructions)
             .add_systems(OnExit(GameState::Instructions), cleanup_instructions)
             .add_systems(OnEnter(GameState::GameOver), setup_game_over)
-            .add_systems(OnEnter(GameState::Won), setup_won);
+            .add_systems(OnExit(GameState::GameOver), cleanup_match_result)
+            .add_systems(OnEnter(GameState::Won), setup_won)
+            .add_systems(OnExit(GameState::Won), cleanup_match_result);
     }
 }
 
 #[derive(Component, Clone)]
-struct ButtonColors {
-    normal: Color,
-    hovered: Color,
+pub struct ButtonColors {
+    pub normal: Color,
+    pub hovered: Color,
 }
 
 impl Default for ButtonColors {
@@ -42,12 +64,24 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands, ui_assets: Res<UiAssets>, mut init: Local<bool>) {
+fn setup_menu(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    settings: Res<MatchSettings>,
+    replay: Option<Res<Replay>>,
+    mut init: Local<bool>,
+) {
     info!("menu");
     if !*init {
         commands.spawn(Camera2dBundle::default());
+        commands.spawn(SpriteBundle {
+            texture: ui_assets.background.clone(),
+            transform: Transform::from_xyz(0.0, 0.0, -100.0),
+            ..Default::default()
+        });
         *init = true;
     }
+    let has_saved_match = matches!(save::load(), Ok(Some(_)));
     commands
         .spawn((
             NodeBundle {
@@ -91,6 +125,38 @@ fn setup_menu(mut commands: Commands, ui_assets: Res<UiAssets>, mut init: Local<
                         },
                     ));
                 });
+            /* A replay is played with the settings it was recorded with */
+            if replay.is_none() {
+                spawn_difficulty_menu(children, &settings);
+            }
+            if has_saved_match {
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(200.0),
+                                height: Val::Px(50.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: button_colors.normal.into(),
+                            ..Default::default()
+                        },
+                        button_colors.clone(),
+                        ContinueMatch,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "Continue",
+                            TextStyle {
+                                font_size: 40.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
             children
                 .spawn((
                     ButtonBundle {
@@ -212,12 +278,313 @@ fn setup_menu(mut commands: Commands, ui_assets: Res<UiAssets>, mut init: Local<
                     });
                 });
         });
+}
+
+/* Difficulty */
+const SELECTED_DIFFICULTY_COLOR: Color = Color::rgb(0.2, 0.45, 0.2);
+
+/// Selects a difficulty preset, [`Difficulty::Custom`] keeps the current values and shows them for adjustment
+#[derive(Component)]
+struct DifficultyButton(Difficulty);
+
+/// Values of the [`MatchSettings`] that can be adjusted for [`Difficulty::Custom`]
+#[derive(Debug, Clone, Copy)]
+enum CustomSetting {
+    StartingGold,
+    CastleHealth,
+    EnemyUpgradeLevel,
+    WaveGold,
+    WaveDelay,
+}
+
+impl CustomSetting {
+    const ALL: [Self; 5] = [
+        Self::StartingGold,
+        Self::CastleHealth,
+        Self::EnemyUpgradeLevel,
+        Self::WaveGold,
+        Self::WaveDelay,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            CustomSetting::StartingGold => "Starting gold",
+            CustomSetting::CastleHealth => "Castle health",
+            CustomSetting::EnemyUpgradeLevel => "Enemy upgrades",
+            CustomSetting::WaveGold => "Enemy wave gold",
+            CustomSetting::WaveDelay => "Wave delay",
+        }
+    }
+
+    fn value(&self, settings: &MatchSettings) -> String {
+        match self {
+            CustomSetting::StartingGold => settings.starting_gold.to_string(),
+            CustomSetting::CastleHealth => format!("{:.0}", settings.castle_health),
+            CustomSetting::EnemyUpgradeLevel => settings.enemy_upgrade_level.to_string(),
+            CustomSetting::WaveGold => format!("x{:.1}", settings.wave_gold),
+            CustomSetting::WaveDelay => format!("x{:.1}", settings.wave_delay),
+        }
+    }
+
+    /// Steps the value up or down within sensible bounds
+    fn adjust(&self, settings: &mut MatchSettings, increase: bool) {
+        let step = |value: f32, amount: f32, min: f32, max: f32| {
+            let value = if increase {
+                value + amount
+            } else {
+                value - amount
+            };
+            /* Round away the float error of repeated steps */
+            ((value / amount).round() * amount).clamp(min, max)
+        };
+        match self {
+            CustomSetting::StartingGold => {
+                settings.starting_gold =
+                    step(settings.starting_gold as f32, 10.0, 0.0, 500.0) as usize
+            }
+            CustomSetting::CastleHealth => {
+                settings.castle_health = step(settings.castle_health, 250.0, 250.0, 5000.0)
+            }
+            CustomSetting::EnemyUpgradeLevel => {
+                settings.enemy_upgrade_level =
+                    step(settings.enemy_upgrade_level as f32, 1.0, 0.0, 10.0) as usize
+            }
+            CustomSetting::WaveGold => settings.wave_gold = step(settings.wave_gold, 0.1, 0.1, 3.0),
+            CustomSetting::WaveDelay => {
+                settings.wave_delay = step(settings.wave_delay, 0.1, 0.5, 2.0)
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct AdjustSettingButton {
+    setting: CustomSetting,
+    increase: bool,
+}
+
+#[derive(Component)]
+struct CustomSettingText(CustomSetting);
+
+/// Only shown for [`Difficulty::Custom`]
+#[derive(Component)]
+struct CustomSettingsPanel;
 
-    commands.spawn(SpriteBundle {
-        texture: ui_assets.background.clone(),
-        transform: Transform::from_xyz(0.0, 0.0, -100.0),
+/// The selected difficulty stays highlighted
+fn difficulty_button_colors(difficulty: Difficulty, settings: &MatchSettings) -> ButtonColors {
+    if difficulty == settings.difficulty {
+        ButtonColors {
+            normal: SELECTED_DIFFICULTY_COLOR,
+            ..default()
+        }
+    } else {
+        ButtonColors::default()
+    }
+}
+
+fn spawn_difficulty_menu(children: &mut ChildBuilder, settings: &MatchSettings) {
+    let text_style = TextStyle {
+        font_size: 25.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+        ..default()
+    };
+    let small_button = |width: f32| Style {
+        width: Val::Px(width),
+        height: Val::Px(36.0),
+        margin: UiRect::all(Val::Px(4.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
         ..Default::default()
-    });
+    };
+
+    children
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                margin: UiRect::vertical(Val::Px(8.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for difficulty in Difficulty::PRESETS
+                .into_iter()
+                .chain(std::iter::once(Difficulty::Custom))
+            {
+                let button_colors = difficulty_button_colors(difficulty, settings);
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: small_button(110.0),
+                            background_color: button_colors.normal.into(),
+                            ..Default::default()
+                        },
+                        button_colors,
+                        DifficultyButton(difficulty),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{:?}", difficulty),
+                            text_style.clone(),
+                        ));
+                    });
+            }
+        });
+
+    children
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: if settings.difficulty == Difficulty::Custom {
+                        Display::Flex
+                    } else {
+                        Display::None
+                    },
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::End,
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            CustomSettingsPanel,
+        ))
+        .with_children(|parent| {
+            for setting in CustomSetting::ALL {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            setting.label(),
+                            text_style.clone(),
+                        ));
+                        spawn_adjust_setting_button(
+                            parent,
+                            AdjustSettingButton {
+                                setting,
+                                increase: false,
+                            },
+                            small_button(36.0),
+                            &text_style,
+                        );
+                        parent.spawn((
+                            TextBundle::from_section(setting.value(settings), text_style.clone())
+                                .with_style(Style {
+                                    width: Val::Px(80.0),
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                })
+                                .with_text_alignment(TextAlignment::Center),
+                            CustomSettingText(setting),
+                        ));
+                        spawn_adjust_setting_button(
+                            parent,
+                            AdjustSettingButton {
+                                setting,
+                                increase: true,
+                            },
+                            small_button(36.0),
+                            &text_style,
+                        );
+                    });
+            }
+        });
+}
+
+fn spawn_adjust_setting_button(
+    parent: &mut ChildBuilder,
+    adjust_setting_button: AdjustSettingButton,
+    style: Style,
+    text_style: &TextStyle,
+) {
+    let label = if adjust_setting_button.increase {
+        "+"
+    } else {
+        "-"
+    };
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style,
+                background_color: button_colors.normal.into(),
+                ..Default::default()
+            },
+            button_colors,
+            adjust_setting_button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn click_difficulty_button(
+    interaction_query: Query<(&Interaction, &DifficultyButton), Changed<Interaction>>,
+    mut settings: ResMut<MatchSettings>,
+) {
+    for (interaction, difficulty_button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match difficulty_button.0 {
+            Difficulty::Custom => settings.difficulty = Difficulty::Custom,
+            preset => *settings = MatchSettings::preset(preset),
+        }
+    }
+}
+
+fn click_adjust_setting_button(
+    interaction_query: Query<(&Interaction, &AdjustSettingButton), Changed<Interaction>>,
+    mut settings: ResMut<MatchSettings>,
+) {
+    for (interaction, adjust_setting_button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            adjust_setting_button
+                .setting
+                .adjust(&mut settings, adjust_setting_button.increase);
+            settings.difficulty = Difficulty::Custom;
+        }
+    }
+}
+
+fn update_difficulty_menu(
+    settings: Res<MatchSettings>,
+    mut difficulty_buttons: Query<(
+        &DifficultyButton,
+        &Interaction,
+        &mut ButtonColors,
+        &mut BackgroundColor,
+    )>,
+    mut custom_settings_panel: Query<&mut Style, With<CustomSettingsPanel>>,
+    mut custom_setting_texts: Query<(&CustomSettingText, &mut Text)>,
+) {
+    for (difficulty_button, interaction, mut button_colors, mut background_color) in
+        &mut difficulty_buttons
+    {
+        *button_colors = difficulty_button_colors(difficulty_button.0, &settings);
+        if *interaction == Interaction::None {
+            *background_color = button_colors.normal.into();
+        }
+    }
+
+    for mut style in &mut custom_settings_panel {
+        style.display = if settings.difficulty == Difficulty::Custom {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+
+    for (custom_setting_text, mut text) in &mut custom_setting_texts {
+        text.sections[0].value = custom_setting_text.0.value(&settings);
+    }
 }
 
 #[derive(Component)]
@@ -226,8 +593,18 @@ struct ChangeState(GameState);
 #[derive(Component)]
 struct OpenLink(&'static str);
 
+/// Restores the saved match
+#[derive(Component)]
+struct ContinueMatch;
+
+/// Tears down the finished match and starts a new one
+#[derive(Component)]
+struct Restart;
+
 fn click_menu_button(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
+    mut restartmatch_evw: EventWriter<RestartMatch>,
     mut interaction_query: Query<
         (
             &Interaction,
@@ -235,15 +612,30 @@ fn click_menu_button(
             &ButtonColors,
             Option<&ChangeState>,
             Option<&OpenLink>,
+            Option<&ContinueMatch>,
+            Option<&Restart>,
         ),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color, button_colors, change_state, open_link) in &mut interaction_query {
+    for (interaction, mut color, button_colors, change_state, open_link, continue_match, restart) in
+        &mut interaction_query
+    {
         match *interaction {
             Interaction::Pressed => {
                 if let Some(state) = change_state {
                     next_state.set(state.0.clone());
+                } else if restart.is_some() {
+                    restartmatch_evw.send(RestartMatch);
+                } else if continue_match.is_some() {
+                    match save::load() {
+                        Ok(Some(save_game)) => {
+                            commands.insert_resource(RestoreMatch(save_game));
+                            next_state.set(GameState::Playing);
+                        }
+                        Ok(None) => warn!("No saved match to continue"),
+                        Err(error) => warn!("Could not load saved match: {}", error),
+                    }
                 } else if let Some(link) = open_link {
                     if let Err(error) = webbrowser::open(link.0) {
                         warn!("Failed to open link {error:?}");
@@ -289,7 +681,7 @@ fn setup_instructions(mut commands: Commands) {
         ))
         .with_children(|parent| {
             parent.spawn(TextBundle::from_section(
-                "Destroy the enemy castle to your right.\nClick any of the buttons on the left side.\nEach button displays information in the top left (like level or how many units are queued up) and the cost in the top right.\nFrom top to bottom: Queue up a soldier, upgrade spawn interval, upgrade Shield, upgrade attack.\nUpgrades go up to level 10.",
+                "Destroy the enemy castle to your right.\nPick a difficulty below the play button, Custom lets you adjust starting gold, castle health and the enemy waves.\nClick any of the buttons on the left side.\nEach button displays information in the top left (like level or how many units are queued up) and the cost in the top right.\nFrom top to bottom: Queue up a soldier, queue up an archer, queue up a knight, upgrade spawn interval, upgrade shield (armor), upgrade attack, build a tower, upgrade click damage, build a farm, build a mine.\nQueued units are shown at the top, up to 20 at a time, click one to cancel it and get its gold back.\nYou earn gold every few seconds, farms and mines raise that income and banked gold earns interest.\nUpgrades go up to level 10, shield and attack upgrades also strengthen the units already on the battlefield. Towers shoot enemies in range and can be destroyed.\nPress T or the tech button to open the tech tree, archers and knights have to be researched before they can be queued.\nRight click sets a rally point your units gather at, shift + right click also calls back the units on the battlefield and R sends them to attack.\nDrag with the left mouse button to select your units, then right click to move or attack, H to hold position and B to return to the castle.\nClick enemy units to damage them.\nPress 1, 2 or 3 or the buttons at the bottom to arm a fireball, heal zone or freeze spell, then left click to cast it with mana.\nPress Escape or the pause button to pause.",
                 TextStyle {
                     font_size: 40.0,
                     color: Color::rgb(0.9, 0.9, 0.9),
@@ -352,48 +744,74 @@ fn cleanup_instructions(mut commands: Commands, instructions: Query<Entity, With
     }
 }
 
+/// The game over or won screen
+#[derive(Component)]
+struct MatchResult;
+
 fn setup_game_over(
     mut commands: Commands,
     mut velocities: Query<&mut LinearVelocity>,
     wave: Res<Wave>,
+    kill_statistics: Res<KillStatistics>,
+    ledger: Res<GoldLedger>,
 ) {
     stop_movement(&mut velocities);
-
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(60.0),
-                    height: Val::Percent(80.0),
-                    top: Val::Percent(10.0),
-                    left: Val::Percent(20.0),
-                    flex_direction: FlexDirection::Column,
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                ..default()
-            },
-            Menu,
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                format!(
-                    "GAME OVER\nYour castle was destroyed in wave {}!",
-                    wave.level
-                ),
-                TextStyle {
-                    font_size: 100.0,
-                    color: Color::rgb(0.0, 0.0, 0.0),
-                    ..default()
-                },
-            ));
-        });
+    spawn_match_result(
+        &mut commands,
+        format!(
+            "GAME OVER\nYour castle was destroyed in wave {}!\n{}\n{}",
+            wave.level,
+            kill_summary(&kill_statistics),
+            gold_summary(&ledger)
+        ),
+    );
 }
 
-fn setup_won(mut commands: Commands, mut velocities: Query<&mut LinearVelocity>, wave: Res<Wave>) {
+fn setup_won(
+    mut commands: Commands,
+    mut velocities: Query<&mut LinearVelocity>,
+    wave: Res<Wave>,
+    kill_statistics: Res<KillStatistics>,
+    ledger: Res<GoldLedger>,
+) {
     stop_movement(&mut velocities);
+    spawn_match_result(
+        &mut commands,
+        format!(
+            "YOU WON\nYou destroyed the enemy castle in wave {}!\n{}\n{}",
+            wave.level,
+            kill_summary(&kill_statistics),
+            gold_summary(&ledger)
+        ),
+    );
+}
 
+fn kill_summary(kill_statistics: &KillStatistics) -> String {
+    format!(
+        "Enemies killed: {}, units lost: {}",
+        kill_statistics.deaths(Faction::Enemy),
+        kill_statistics.deaths(Faction::Ally)
+    )
+}
+
+/// Totals and the category most of the gold went to
+fn gold_summary(ledger: &GoldLedger) -> String {
+    let top_spending = GoldCategory::ALL
+        .into_iter()
+        .filter(|category| ledger.spent(*category) > 0)
+        .max_by_key(|category| ledger.spent(*category));
+    match top_spending {
+        Some(category) => format!(
+            "Gold earned: {}, spent: {} (mostly on {:?})",
+            ledger.total_earned(),
+            ledger.total_spent(),
+            category
+        ),
+        None => format!("Gold earned: {}, spent: 0", ledger.total_earned()),
+    }
+}
+
+fn spawn_match_result(commands: &mut Commands, message: String) {
     commands
         .spawn((
             NodeBundle {
@@ -405,27 +823,85 @@ fn setup_won(mut commands: Commands, mut velocities: Query<&mut LinearVelocity>,
                     flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
                     ..default()
                 },
                 ..default()
             },
-            Menu,
+            MatchResult,
         ))
         .with_children(|parent| {
             parent.spawn(TextBundle::from_section(
-                format!(
-                    "YOU WON\nYou destroyed the enemy castle in wave {}!",
-                    wave.level
-                ),
+                message,
                 TextStyle {
                     font_size: 100.0,
                     color: Color::rgb(0.0, 0.0, 0.0),
                     ..default()
                 },
             ));
+
+            let button_colors = ButtonColors::default();
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(240.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors.clone(),
+                    Restart,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Restart",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(240.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::Menu),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Main Menu",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
         });
 }
 
+fn cleanup_match_result(mut commands: Commands, match_result: Query<Entity, With<MatchResult>>) {
+    for entity in match_result.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn stop_movement(velocities: &mut Query<&mut LinearVelocity>) {
     for mut velocity in velocities {
         velocity.0 = Vec2::ZERO;
@@ -1,15 +1,19 @@
 pub mod game_ui;
 pub mod menu;
+pub mod pause;
+pub mod selection;
 
 use bevy::prelude::*;
 
-use self::{game_ui::GameUiPlugin, menu::MenuPlugin};
+use self::{
+    game_ui::GameUiPlugin, menu::MenuPlugin, pause::PauseMenuPlugin, selection::SelectionPlugin,
+};
 
 pub struct InternalUiPlugin;
 
 // This plugin is responsible to control the game audio
 impl Plugin for InternalUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((MenuPlugin, GameUiPlugin));
+        app.add_plugins((MenuPlugin, GameUiPlugin, PauseMenuPlugin, SelectionPlugin));
     }
 }
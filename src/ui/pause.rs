@@ -0,0 +1,213 @@
+use bevy::{
+    audio::{GlobalVolume, VolumeLevel},
+    prelude::*,
+    ui::FocusPolicy,
+};
+
+use crate::{session::RestartMatch, GameState};
+
+use super::menu::ButtonColors;
+
+pub struct PauseMenuPlugin;
+
+/// This plugin is responsible for the pause menu
+/// The menu is only drawn during the State `GameState::Paused` and is removed when that state is exited
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Paused), setup_pause_menu)
+            .add_systems(
+                Update,
+                (click_pause_menu_button, update_volume_text).run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(OnExit(GameState::Paused), cleanup_pause_menu);
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum PauseMenuButton {
+    Resume,
+    Restart,
+    Settings,
+    QuitToMenu,
+    VolumeDown,
+    VolumeUp,
+}
+
+/// Hidden until the settings button is pressed
+#[derive(Component)]
+struct SettingsPanel;
+
+#[derive(Component)]
+struct VolumeText;
+
+fn setup_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                /* Draw above the game ui and keep clicks away from it */
+                focus_policy: FocusPolicy::Block,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 80.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+            for (button, label) in [
+                (PauseMenuButton::Resume, "Resume"),
+                (PauseMenuButton::Restart, "Restart"),
+                (PauseMenuButton::Settings, "Settings"),
+                (PauseMenuButton::QuitToMenu, "Quit to Menu"),
+            ] {
+                spawn_button(children, button, label, 280.0);
+            }
+            children
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            display: Display::None,
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    SettingsPanel,
+                ))
+                .with_children(|children| {
+                    spawn_button(children, PauseMenuButton::VolumeDown, "-", 50.0);
+                    children.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 40.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        VolumeText,
+                    ));
+                    spawn_button(children, PauseMenuButton::VolumeUp, "+", 50.0);
+                });
+        });
+}
+
+fn spawn_button(children: &mut ChildBuilder, button: PauseMenuButton, label: &str, width: f32) {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: button_colors.normal.into(),
+                ..Default::default()
+            },
+            button_colors,
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn click_pause_menu_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            &PauseMenuButton,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut settings_panels: Query<&mut Style, With<SettingsPanel>>,
+    mut restartmatch_evw: EventWriter<RestartMatch>,
+    mut global_volume: ResMut<GlobalVolume>,
+    audio_sinks: Query<&AudioSink>,
+) {
+    for (interaction, mut color, button_colors, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => match button {
+                PauseMenuButton::Resume => next_state.set(GameState::Playing),
+                PauseMenuButton::Restart => restartmatch_evw.send(RestartMatch),
+                PauseMenuButton::Settings => {
+                    for mut style in &mut settings_panels {
+                        style.display = match style.display {
+                            Display::None => Display::Flex,
+                            _ => Display::None,
+                        };
+                    }
+                }
+                PauseMenuButton::QuitToMenu => next_state.set(GameState::Menu),
+                PauseMenuButton::VolumeDown | PauseMenuButton::VolumeUp => {
+                    let delta = match button {
+                        PauseMenuButton::VolumeUp => 0.1,
+                        _ => -0.1,
+                    };
+                    let volume = (global_volume.volume.get() + delta).clamp(0.0, 1.0);
+                    global_volume.volume = VolumeLevel::new(volume);
+                    /* The global volume only applies to newly spawned sounds */
+                    for audio_sink in &audio_sinks {
+                        audio_sink.set_volume(volume);
+                    }
+                }
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn update_volume_text(
+    mut volume_texts: Query<&mut Text, With<VolumeText>>,
+    global_volume: Res<GlobalVolume>,
+) {
+    for mut text in &mut volume_texts {
+        text.sections[0].value = format!("Volume {:.0}%", global_volume.volume.get() * 100.0);
+    }
+}
+
+fn cleanup_pause_menu(mut commands: Commands, pause_menu: Query<Entity, With<PauseMenu>>) {
+    for entity in &pause_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, ButtonState},
+    prelude::*,
+};
+use bevy_xpbd_2d::{components::ColliderParent, prelude::SpatialQuery};
+
+use crate::{
+    click::enemy_unit_at,
+    command::{PendingPlayerActions, PlayerAction},
+    common::viewport_to_world_position,
+    units::{
+        order::{Selected, UnitOrder},
+        UnitKind,
+    },
+    GameState,
+};
+
+use super::game_ui::ArmedSpell;
+
+pub struct SelectionPlugin;
+
+/// This plugin handles box selection of allied units and ordering them around
+/// Drag with the left mouse button to select, right click to move or attack, H to hold position and B to return to the castle
+/// Left clicking an enemy unit damages it instead of changing the selection, while a spell is armed it casts the spell
+/// Selection is only possible during the State `GameState::Playing`
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DragStart>().add_systems(
+            Update,
+            (
+                drag_selection,
+                emit_order_actions,
+                draw_selection_box,
+                highlight_selected,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// A click without dragging selects everything this close to the cursor
+const CLICK_SELECTION_RADIUS: f32 = 8.0;
+
+/// Where the left mouse button was pressed, while it is held
+#[derive(Debug, Default, Resource)]
+struct DragStart(Option<Vec2>);
+
+fn cursor_world_position(
+    windows: &Query<&Window>,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+    window: Entity,
+) -> Option<Vec2> {
+    let window = windows.get(window).ok()?;
+    let (camera, camera_transform) = cameras.get_single().ok()?;
+    viewport_to_world_position(window, camera, camera_transform)
+}
+
+fn drag_selection(
+    mut mousebtn_evr: EventReader<MouseButtonInput>,
+    mut drag_start: ResMut<DragStart>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    interactions: Query<&Interaction>,
+    spatial_query: SpatialQuery,
+    collider_parents: Query<&ColliderParent>,
+    units: Query<&GlobalTransform, With<UnitKind>>,
+    mut armed_spell: ResMut<ArmedSpell>,
+) {
+    for ev in mousebtn_evr.read() {
+        if ev.button != MouseButton::Left {
+            continue;
+        }
+
+        match ev.state {
+            ButtonState::Pressed => {
+                /* Clicks on the ui are not meant for the battlefield */
+                let over_ui = interactions
+                    .iter()
+                    .any(|interaction| *interaction != Interaction::None);
+                let cursor = if over_ui {
+                    None
+                } else {
+                    cursor_world_position(&windows, &cameras, ev.window)
+                };
+
+                if let (Some(spell), Some(cursor)) = (armed_spell.0, cursor) {
+                    player_actions.send(PlayerAction::CastSpell {
+                        spell,
+                        x: cursor.x.round() as i32,
+                        y: cursor.y.round() as i32,
+                    });
+                    armed_spell.0 = None;
+                    drag_start.0 = None;
+                } else {
+                    drag_start.0 = cursor;
+                }
+            }
+            ButtonState::Released => {
+                let Some(start) = drag_start.0.take() else {
+                    continue;
+                };
+                let end = cursor_world_position(&windows, &cameras, ev.window).unwrap_or(start);
+                let area = Rect::from_corners(start, end);
+                let is_click =
+                    area.width() < CLICK_SELECTION_RADIUS && area.height() < CLICK_SELECTION_RADIUS;
+
+                if is_click
+                    && enemy_unit_at(&spatial_query, &collider_parents, &units, end).is_some()
+                {
+                    player_actions.send(PlayerAction::ClickDamage {
+                        x: end.x.round() as i32,
+                        y: end.y.round() as i32,
+                    });
+                    continue;
+                }
+
+                let area = if is_click {
+                    area.inset(CLICK_SELECTION_RADIUS)
+                } else {
+                    area
+                };
+
+                player_actions.send(PlayerAction::SelectUnits {
+                    min_x: area.min.x.floor() as i32,
+                    min_y: area.min.y.floor() as i32,
+                    max_x: area.max.x.ceil() as i32,
+                    max_y: area.max.y.ceil() as i32,
+                });
+            }
+        }
+    }
+}
+
+fn emit_order_actions(
+    mut mousebtn_evr: EventReader<MouseButtonInput>,
+    mut keyboard_evr: EventReader<KeyboardInput>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    selected: Query<(), With<Selected>>,
+) {
+    /* Without a selection right click sets the rally point instead */
+    if selected.is_empty() {
+        mousebtn_evr.clear();
+        keyboard_evr.clear();
+        return;
+    }
+
+    for ev in mousebtn_evr.read() {
+        if ev.button != MouseButton::Right || ev.state != ButtonState::Pressed {
+            continue;
+        }
+        if let Some(world_position) = cursor_world_position(&windows, &cameras, ev.window) {
+            player_actions.send(PlayerAction::OrderUnits(UnitOrder::Target {
+                x: world_position.x.round() as i32,
+                y: world_position.y.round() as i32,
+            }));
+        }
+    }
+
+    for ev in keyboard_evr.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+        match ev.key_code {
+            Some(KeyCode::H) => {
+                player_actions.send(PlayerAction::OrderUnits(UnitOrder::HoldPosition))
+            }
+            Some(KeyCode::B) => {
+                player_actions.send(PlayerAction::OrderUnits(UnitOrder::ReturnToCastle))
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw_selection_box(
+    mut gizmos: Gizmos,
+    drag_start: Res<DragStart>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    let Some(start) = drag_start.0 else {
+        return;
+    };
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+
+    if let Some(cursor) = viewport_to_world_position(window, camera, camera_transform) {
+        let area = Rect::from_corners(start, cursor);
+        gizmos.rect_2d(area.center(), 0.0, area.size(), Color::rgb(0.2, 0.9, 0.2));
+    }
+}
+
+fn highlight_selected(mut units: Query<(&mut Sprite, Option<&Selected>), With<UnitKind>>) {
+    for (mut sprite, selected) in &mut units {
+        let color = match selected {
+            Some(_) => Color::rgb(0.5, 1.0, 0.5),
+            None => Color::WHITE,
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -4,8 +4,19 @@ use bevy::prelude::*;
 use bevy_xpbd_2d::components::{
     Collider, ColliderParent, CollidingEntities, CollisionLayers, LinearVelocity, Sensor,
 };
+use rand::Rng;
 
-use crate::GameState;
+use crate::{
+    castle::AllyCastle,
+    command::SimulationSet,
+    common::{
+        rng::GameRng,
+        stats::{Stat, Stats},
+    },
+    GameState,
+};
+
+use super::ranged::RangedAttack;
 
 pub struct BehaviourPlugin;
 
@@ -14,13 +25,14 @@ pub struct BehaviourPlugin;
 impl Plugin for BehaviourPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
+            FixedUpdate,
             (
                 behaviour,
                 behavior_added,
                 enemy_finder,
                 face_velocity_vector,
             )
+                .in_set(SimulationSet::Combat)
                 .run_if(in_state(GameState::Playing)),
         );
     }
@@ -31,7 +43,25 @@ impl Plugin for BehaviourPlugin {
 pub enum Behaviour {
     Wandering(Timer, LinearVelocity),
     MoveToPoint(Vec2),
+    /// Move to the point and stay there
+    HoldPoint(Vec2),
     MoveAndAttack(Entity),
+    /// Ordered by the player, falls back to the `DefaultBehaviour` once the point is reached
+    MoveOrder(Vec2),
+    /// Ordered by the player, falls back to the `DefaultBehaviour` once the target is gone
+    AttackOrder(Entity),
+    /// Ordered by the player, walks back to the ally castle and falls back to the `DefaultBehaviour` once it is there
+    ReturnToCastle,
+}
+
+impl Behaviour {
+    /// Orders are not interrupted by enemies coming into range
+    pub fn is_order(&self) -> bool {
+        matches!(
+            self,
+            Self::MoveOrder(_) | Self::AttackOrder(_) | Self::ReturnToCastle
+        )
+    }
 }
 
 impl Default for Behaviour {
@@ -43,25 +73,43 @@ impl Default for Behaviour {
     }
 }
 
-fn behaviour(
+pub fn behaviour(
     mut query: Query<(
         Entity,
         &mut LinearVelocity,
         &mut Behaviour,
         Option<&DefaultBehaviour>,
         &CollidingEntities,
+        Option<&RangedAttack>,
+        &Stats,
     )>,
     transforms: Query<&GlobalTransform>,
     time: Res<Time>,
-    mut direction: Local<Vec2>,
+    mut rng: ResMut<GameRng>,
+    ally_castle: Res<AllyCastle>,
 ) {
-    for (source_entity, mut velocity, mut behaviour, default_behaviour, colliding_entities) in
-        query.iter_mut()
+    for (
+        source_entity,
+        mut velocity,
+        mut behaviour,
+        default_behaviour,
+        colliding_entities,
+        ranged_attack,
+        stats,
+    ) in query.iter_mut()
     {
+        let move_speed = stats.get(Stat::MoveSpeed);
+        /* Stunned units keep their behaviour until they can move again */
+        if move_speed == 0.0 {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+
         let inner_behaviour = behaviour.as_mut();
         match inner_behaviour {
             Behaviour::Wandering(ref mut timer, ref mut saved_velocity) => {
-                wandering(&time, timer, &mut velocity, saved_velocity, &mut direction)
+                wandering(&time, timer, &mut velocity, saved_velocity, &mut rng);
+                velocity.0 *= stats.ratio(Stat::MoveSpeed);
             }
             Behaviour::MoveToPoint(dst_point) => {
                 let src_point = transforms
@@ -69,9 +117,55 @@ fn behaviour(
                     .unwrap()
                     .translation()
                     .truncate();
-                move_to_point(&mut velocity, &src_point, dst_point, 50.0);
+                move_to_point(&mut velocity, &src_point, dst_point, move_speed);
             }
-            Behaviour::MoveAndAttack(entity) => {
+            Behaviour::HoldPoint(dst_point) => {
+                let src_point = transforms
+                    .get(source_entity)
+                    .unwrap()
+                    .translation()
+                    .truncate();
+                if src_point.distance(*dst_point) > HOLD_RADIUS {
+                    move_to_point(&mut velocity, &src_point, dst_point, move_speed);
+                } else {
+                    velocity.0 = Vec2::ZERO;
+                }
+            }
+            Behaviour::MoveOrder(dst_point) => {
+                let src_point = transforms
+                    .get(source_entity)
+                    .unwrap()
+                    .translation()
+                    .truncate();
+                if src_point.distance(*dst_point) > HOLD_RADIUS {
+                    move_to_point(&mut velocity, &src_point, dst_point, move_speed);
+                } else {
+                    velocity.0 = Vec2::ZERO;
+                    *behaviour = default_behaviour.map_or(Behaviour::default(), |b| b.0.clone());
+                }
+            }
+            Behaviour::ReturnToCastle => {
+                let src_point = transforms
+                    .get(source_entity)
+                    .unwrap()
+                    .translation()
+                    .truncate();
+                let Some(dst_point) = ally_castle
+                    .0
+                    .and_then(|entity| transforms.get(entity).ok())
+                    .map(|transform| transform.translation().truncate())
+                else {
+                    velocity.0 = Vec2::ZERO;
+                    continue;
+                };
+                if src_point.distance(dst_point) > RETURN_DISTANCE {
+                    move_to_point(&mut velocity, &src_point, &dst_point, move_speed);
+                } else {
+                    velocity.0 = Vec2::ZERO;
+                    *behaviour = default_behaviour.map_or(Behaviour::default(), |b| b.0.clone());
+                }
+            }
+            Behaviour::MoveAndAttack(entity) | Behaviour::AttackOrder(entity) => {
                 let src_point = transforms
                     .get(source_entity)
                     .unwrap()
@@ -79,12 +173,17 @@ fn behaviour(
                     .truncate();
                 if let Ok(dst_transform) = transforms.get(*entity) {
                     let dst_point = dst_transform.translation().truncate();
+                    if ranged_attack.is_some_and(|ranged_attack| ranged_attack.target_in_range) {
+                        velocity.0 = Vec2::ZERO;
+                        continue;
+                    }
                     move_and_attack(
                         &mut velocity,
                         &src_point,
                         &dst_point,
                         colliding_entities,
                         entity,
+                        move_speed,
                     );
                 } else {
                     velocity.0 = Vec2::ZERO;
@@ -95,16 +194,20 @@ fn behaviour(
     }
 }
 
+/// Units holding a point stop once they are this close to it
+const HOLD_RADIUS: f32 = 20.0;
+/// Units returning to the castle stop this far from its center, just outside of its collider
+const RETURN_DISTANCE: f32 = 170.0;
+
 fn wandering(
     time: &Time,
     timer: &mut Timer,
     velocity: &mut LinearVelocity,
     saved_velocity: &mut LinearVelocity,
-    direction: &mut Vec2,
+    rng: &mut GameRng,
 ) {
     if timer.tick(time.delta()).just_finished() {
-        *direction = Vec2::new((direction.x + 0.5) % 2.0, (direction.y + 0.5) % 2.0);
-        let vector = *direction - Vec2::new(1.0, 1.0);
+        let vector = wandering_direction(rng);
         velocity.0 = vector * 20.0;
         saved_velocity.0 = vector * 20.0;
     }
@@ -112,6 +215,11 @@ fn wandering(
     *velocity = *saved_velocity;
 }
 
+/// Random direction with each component in [-1, 1]
+fn wandering_direction(rng: &mut GameRng) -> Vec2 {
+    Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0))
+}
+
 fn move_to_point(
     velocity: &mut LinearVelocity,
     src_point: &Vec2,
@@ -129,10 +237,11 @@ fn move_and_attack(
     dst_point: &Vec2,
     colliding_entities: &CollidingEntities,
     dst_entity: &Entity,
+    move_speed: f32,
 ) {
     if !colliding_entities.contains(dst_entity) {
         let velocity_scale =
-            (src_point.distance(*dst_point).min(100.0).max(1.0) - 1.0) / 100.0 * 50.0;
+            (src_point.distance(*dst_point).min(100.0).max(1.0) - 1.0) / 100.0 * move_speed;
         move_to_point(velocity, src_point, dst_point, velocity_scale);
     }
 }
@@ -162,7 +271,7 @@ fn enemy_finder(
         {
             let src_point = src_transform.translation().truncate();
             /* Behaviour is Wandering and our EnemyFinder actually collides with somethings */
-            if !colliding_entities.is_empty() {
+            if !colliding_entities.is_empty() && !behaviour.is_order() {
                 /* Get the shortest distance Entity */
                 if let Some((target, _)) = colliding_entities
                     .iter()
@@ -195,13 +304,11 @@ pub struct DefaultBehaviour(pub Behaviour);
 
 fn behavior_added(
     mut behaviours: Query<&mut Behaviour, Added<Behaviour>>,
-    mut direction: Local<Vec2>,
+    mut rng: ResMut<GameRng>,
 ) {
     for mut behaviour in &mut behaviours {
         if let Behaviour::Wandering(_, ref mut velocity) = behaviour.as_mut() {
-            *direction = Vec2::new((direction.x + 0.5) % 2.0, (direction.y + 0.5) % 2.0);
-            let vector = *direction - Vec2::new(1.0, 1.0);
-            velocity.0 = vector * 20.0;
+            velocity.0 = wandering_direction(&mut rng) * 20.0;
         }
     }
 }
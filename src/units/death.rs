@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    castle::economy::{GoldCategory, Treasury},
+    command::SimulationSet,
+    common::{attributes::HealthDeltaSource, Faction},
+    loading::AudioAssets,
+    session::{MatchEntity, MatchSetupSet},
+    GameState,
+};
+
+use super::{definition::UnitRegistry, UnitKind};
+
+pub struct DeathPlugin;
+
+/// This plugin handles everything that happens when a unit dies, like bounties, kill statistics and death effects
+/// Death logic is only active during the State `GameState::Playing`
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UnitDied>()
+            .init_resource::<KillStatistics>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_kill_statistics.in_set(MatchSetupSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (award_bounty, count_kills)
+                    .in_set(SimulationSet::Death)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (spawn_death_effect, animate_death_effect).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The health of a unit dropped to zero, sent right before it is despawned
+#[derive(Debug, Event)]
+pub struct UnitDied {
+    pub entity: Entity,
+    pub faction: Faction,
+    pub kind: UnitKind,
+    /// Dealt the final blow, `None` if nobody is credited for it
+    pub killer: Option<HealthDeltaSource>,
+    pub position: Vec2,
+}
+
+/// Units that died during the current match
+#[derive(Debug, Default, Resource)]
+pub struct KillStatistics {
+    deaths: HashMap<(Faction, UnitKind), usize>,
+    kills: HashMap<Faction, usize>,
+}
+
+impl KillStatistics {
+    /// Units of the faction that died, no matter who killed them
+    pub fn deaths(&self, faction: Faction) -> usize {
+        self.deaths
+            .iter()
+            .filter(|((dead_faction, _), _)| *dead_faction == faction)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Units the units, towers and castle of the faction dealt the final blow to
+    pub fn kills(&self, faction: Faction) -> usize {
+        self.kills.get(&faction).copied().unwrap_or_default()
+    }
+}
+
+fn reset_kill_statistics(mut kill_statistics: ResMut<KillStatistics>) {
+    *kill_statistics = KillStatistics::default();
+}
+
+fn award_bounty(
+    mut unitdied_evr: EventReader<UnitDied>,
+    mut treasury: Treasury,
+    unit_registry: UnitRegistry,
+) {
+    for ev in unitdied_evr.read() {
+        if ev.faction == Faction::Enemy {
+            treasury.earn(
+                unit_registry.get(&ev.kind).bounty,
+                GoldCategory::Bounties,
+                Some(ev.position),
+            );
+        }
+    }
+}
+
+fn count_kills(
+    mut unitdied_evr: EventReader<UnitDied>,
+    mut kill_statistics: ResMut<KillStatistics>,
+) {
+    for ev in unitdied_evr.read() {
+        *kill_statistics
+            .deaths
+            .entry((ev.faction, ev.kind))
+            .or_default() += 1;
+        if let Some(killer) = ev.killer {
+            *kill_statistics.kills.entry(killer.faction).or_default() += 1;
+        }
+    }
+}
+
+/// Fading marker left where a unit died
+#[derive(Debug, Component)]
+struct DeathEffect(Timer);
+
+fn spawn_death_effect(
+    mut commands: Commands,
+    mut unitdied_evr: EventReader<UnitDied>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for ev in unitdied_evr.read() {
+        let color = match ev.faction {
+            Faction::Ally => Color::rgba(0.2, 0.4, 1.0, 0.8),
+            Faction::Enemy => Color::rgba(1.0, 0.2, 0.2, 0.8),
+        };
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(16.0)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(ev.position.extend(5.0)),
+                ..Default::default()
+            },
+            AudioBundle {
+                source: audio_assets.hit.clone(),
+                settings: PlaybackSettings::ONCE
+                    .with_speed(0.6)
+                    .with_volume(Volume::new_relative(0.5)),
+            },
+            DeathEffect(Timer::from_seconds(0.5, TimerMode::Once)),
+            MatchEntity,
+        ));
+    }
+}
+
+fn animate_death_effect(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut effects: Query<(Entity, &mut DeathEffect, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut effect, mut transform, mut sprite) in &mut effects {
+        if effect.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        transform.scale = Vec3::splat(1.0 + effect.0.percent());
+        sprite.color.set_a(0.8 * effect.0.percent_left());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use bevy::{asset::LoadContext, ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    common::{
+        damage::DamageType,
+        stats::{Stat, Stats},
+        status::StatusEffectDefinition,
+        Faction,
+    },
+    loading::{DataAssets, RonAsset},
+};
+
+use super::UnitKind;
+
+/// Stats, textures, cost and colliders of every [`UnitKind`]
+/// Loaded from `assets/data/game.units.ron`
+#[derive(Debug, Asset, TypePath, Deserialize)]
+pub struct UnitDefinitions {
+    units: HashMap<UnitKind, UnitDefinition>,
+}
+
+impl RonAsset for UnitDefinitions {
+    const EXTENSIONS: &'static [&'static str] = &["units.ron"];
+
+    fn load_dependencies(&mut self, load_context: &mut LoadContext) {
+        for definition in self.units.values_mut() {
+            definition.textures.load(load_context);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitDefinition {
+    pub cost: usize,
+    /// Gold the player gets when an enemy unit of this kind dies
+    pub bounty: usize,
+    pub health: f32,
+    /// Sprite size in pixels
+    pub size: f32,
+    pub textures: FactionTextures,
+    /// Radius of the physics collider
+    pub collider: f32,
+    pub hurtbox: f32,
+    pub attack: AttackDefinition,
+    /// Seconds between two attacks
+    pub attack_cooldown: f32,
+    /// Pixels per second
+    pub move_speed: f32,
+    #[serde(default)]
+    pub armor: f32,
+    #[serde(default)]
+    pub magic_resist: f32,
+    /// Chance from 0 to 1 that an attack is a critical hit
+    #[serde(default)]
+    pub crit_chance: f32,
+}
+
+impl UnitDefinition {
+    /// Base [`Stats`] of a unit of this kind, without any modifiers
+    pub fn stats(&self) -> Stats {
+        Stats::new([
+            (Stat::MaxHealth, self.health),
+            (Stat::Damage, self.attack.damage()),
+            (Stat::AttackCooldown, self.attack_cooldown),
+            (Stat::MoveSpeed, self.move_speed),
+            (Stat::DetectionRadius, self.attack.enemy_finder()),
+            (Stat::Armor, self.armor),
+            (Stat::MagicResist, self.magic_resist),
+            (Stat::CritChance, self.crit_chance),
+        ])
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub enum AttackDefinition {
+    /// Hits everything in `radius` with a hitbox, chasing enemies found within `enemy_finder`
+    Melee {
+        damage: f32,
+        radius: f32,
+        enemy_finder: f32,
+        #[serde(default)]
+        damage_type: DamageType,
+        /// Applied to everything that is hit
+        #[serde(default)]
+        status_effect: Option<StatusEffectDefinition>,
+    },
+    /// Stops as soon as an enemy is within `range` and fires projectiles at it
+    Ranged {
+        range: f32,
+        projectile: ProjectileDefinition,
+    },
+}
+
+impl AttackDefinition {
+    /// Radius of the EnemyFinder
+    pub fn enemy_finder(&self) -> f32 {
+        match self {
+            AttackDefinition::Melee { enemy_finder, .. } => *enemy_finder,
+            AttackDefinition::Ranged { range, .. } => *range,
+        }
+    }
+
+    /// Damage of a single hit
+    pub fn damage(&self) -> f32 {
+        match self {
+            AttackDefinition::Melee { damage, .. } => *damage,
+            AttackDefinition::Ranged { projectile, .. } => projectile.damage,
+        }
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            AttackDefinition::Melee { damage_type, .. } => *damage_type,
+            AttackDefinition::Ranged { projectile, .. } => projectile.damage_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectileDefinition {
+    pub damage: f32,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    /// Radius of the hitbox
+    pub radius: f32,
+    /// Sprite size in pixels
+    pub size: f32,
+    pub speed: f32,
+    /// Seconds until the projectile despawns if it did not hit anything
+    pub lifetime: f32,
+    /// Applied to whatever the projectile hits
+    #[serde(default)]
+    pub status_effect: Option<StatusEffectDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FactionTextures {
+    ally: String,
+    enemy: String,
+    #[serde(skip)]
+    ally_handle: Handle<Image>,
+    #[serde(skip)]
+    enemy_handle: Handle<Image>,
+}
+
+impl FactionTextures {
+    fn load(&mut self, load_context: &mut LoadContext) {
+        self.ally_handle = load_context.load(self.ally.as_str());
+        self.enemy_handle = load_context.load(self.enemy.as_str());
+    }
+
+    pub fn get(&self, faction: &Faction) -> Handle<Image> {
+        match faction {
+            Faction::Ally => self.ally_handle.clone(),
+            Faction::Enemy => self.enemy_handle.clone(),
+        }
+    }
+}
+
+/// Looks up the [`UnitDefinition`] of a [`UnitKind`] in the loaded [`UnitDefinitions`]
+#[derive(SystemParam)]
+pub struct UnitRegistry<'w> {
+    data_assets: Res<'w, DataAssets>,
+    unit_definitions: Res<'w, Assets<UnitDefinitions>>,
+}
+
+impl UnitRegistry<'_> {
+    pub fn get(&self, kind: &UnitKind) -> &UnitDefinition {
+        self.unit_definitions
+            .get(&self.data_assets.units)
+            .and_then(|unit_definitions| unit_definitions.units.get(kind))
+            .unwrap_or_else(|| panic!("No definition for {:?} in units.ron", kind))
+    }
+}
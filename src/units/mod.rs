@@ -1,22 +1,41 @@
 pub mod behaviour;
+pub mod death;
+pub mod definition;
+pub mod order;
+pub mod rally;
+pub mod ranged;
 pub mod upgrade;
 
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use bevy_xpbd_2d::components::{Collider, CollidingEntities, CollisionLayers, Sensor};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    castle::{AllyCastle, Castle, EnemyCastle, SpawnUnit},
-    common::attributes::Health,
-    common::Faction,
-    loading::{AudioAssets, TextureAssets},
+    castle::{spawn_queue, AllyCastle, EnemyCastle, SpawnUnit},
+    command::SimulationSet,
+    common::attributes::{Health, HealthDeltaSource},
+    common::{
+        damage::Damage,
+        stats::{Stat, Stats},
+        Faction,
+    },
+    loading::AudioAssets,
     physics::hit_detection::{HitBox, HitBoxBundle, HitBoxKind, HurtBoxBundle},
     physics::PhysicsCollisionBundle,
+    session::MatchEntity,
     GameState,
 };
 
 use self::{
-    behaviour::{Behaviour, BehaviourPlugin, DefaultBehaviour, EnemyFinderBundle},
-    upgrade::{AttackCooldownUpgrade, ShieldUpgrade, UpgradePlugin},
+    behaviour::{Behaviour, BehaviourPlugin, DefaultBehaviour, EnemyFinder, EnemyFinderBundle},
+    death::DeathPlugin,
+    definition::{AttackDefinition, UnitDefinition, UnitRegistry},
+    order::OrderPlugin,
+    rally::{RallyPlugin, RallyPoint},
+    ranged::{RangedAttack, RangedPlugin},
+    upgrade::{UnitModifiers, UpgradePlugin},
 };
 
 pub struct UnitPluging;
@@ -25,24 +44,42 @@ pub struct UnitPluging;
 /// Unit logic is only active during the State `GameState::Playing`
 impl Plugin for UnitPluging {
     fn build(&self, app: &mut App) {
-        app.add_plugins((BehaviourPlugin, UpgradePlugin))
-            .add_systems(
-                Update,
-                (
-                    advance_attack_cooldown_timer,
-                    spawn_unit_from_event,
-                    spawn_protection,
-                )
-                    .run_if(in_state(GameState::Playing)),
-            );
+        app.add_plugins((
+            BehaviourPlugin,
+            DeathPlugin,
+            OrderPlugin,
+            RallyPlugin,
+            RangedPlugin,
+            UpgradePlugin,
+        ))
+        .add_systems(
+            FixedUpdate,
+            (
+                advance_attack_cooldown_timer,
+                spawn_unit_from_event.after(spawn_queue),
+                spawn_protection,
+                apply_stats,
+            )
+                .chain()
+                .in_set(SimulationSet::Spawn)
+                .run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
-#[derive(Debug, Component)]
+#[derive(Debug, Clone, Component)]
 pub struct AttackCooldown {
     pub timer: Timer,
 }
 
+impl AttackCooldown {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Repeating),
+        }
+    }
+}
+
 fn advance_attack_cooldown_timer(
     time: Res<Time>,
     mut cooldowns: Query<(&mut AttackCooldown, &mut HitBox)>,
@@ -58,44 +95,69 @@ fn spawn_unit_from_event(
     mut spawnunit_evr: EventReader<SpawnUnit>,
     transforms: Query<&GlobalTransform>,
     mut commands: Commands,
-    textures: Res<TextureAssets>,
+    unit_registry: UnitRegistry,
     mut y: Local<f32>,
-    soldier_shields: Query<&ShieldUpgrade, With<Castle>>,
+    unit_modifiers: Query<&UnitModifiers>,
 ) {
     for ev in spawnunit_evr.read() {
         if let Ok(transform) = transforms.get(ev.origin) {
             let translation = transform.translation();
             *y = (*y + 60.0) % 360.0;
 
-            let health = if let Ok(soldier_shield) = soldier_shields.get(ev.origin) {
-                100.0 + soldier_shield.get()
-            } else {
-                100.0
-            };
+            let definition = unit_registry.get(&ev.kind);
+            let mut stats = definition.stats();
+            if let Ok(unit_modifiers) = unit_modifiers.get(ev.origin) {
+                stats = stats.with_modifiers(unit_modifiers.0.iter());
+            }
 
-            commands
-                .spawn(SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(20.0, 20.0)),
-                        ..Default::default()
-                    },
-                    texture: ev.kind.texture(&ev.faction, &textures),
-                    transform: Transform::from_translation(translation),
-                    ..Default::default()
-                })
-                .insert(Behaviour::MoveToPoint(Vec2::new(0.0, *y - 180.0)))
-                .insert(Health::new(health))
-                .insert(PhysicsCollisionBundle {
-                    collider: Collider::ball(10.0),
-                    ..Default::default()
-                })
-                .insert(Sensor)
-                .insert(SpawnProtection::default())
-                .insert(ev.faction);
+            spawn_unit_body(
+                &mut commands,
+                ev.kind,
+                ev.faction,
+                definition,
+                translation,
+                Health::new(stats.get(Stat::MaxHealth)),
+                stats,
+            )
+            .insert(Behaviour::MoveToPoint(Vec2::new(0.0, *y - 180.0)))
+            .insert(Sensor)
+            .insert(SpawnProtection::default());
         }
     }
 }
 
+/// Spawns sprite, collider, health, stats, kind and faction of a unit, without any behaviour or combat components
+pub fn spawn_unit_body<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    kind: UnitKind,
+    faction: Faction,
+    definition: &UnitDefinition,
+    translation: Vec3,
+    health: Health,
+    stats: Stats,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut entity_commands = commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            custom_size: Some(Vec2::splat(definition.size)),
+            ..Default::default()
+        },
+        texture: definition.textures.get(&faction),
+        transform: Transform::from_translation(translation),
+        ..Default::default()
+    });
+    entity_commands
+        .insert(health)
+        .insert(stats)
+        .insert(PhysicsCollisionBundle {
+            collider: Collider::ball(definition.collider),
+            ..Default::default()
+        })
+        .insert(kind)
+        .insert(faction)
+        .insert(MatchEntity);
+    entity_commands
+}
+
 /* Spawn with this instead */
 #[derive(Debug, Component)]
 pub struct SpawnProtection(Timer);
@@ -111,6 +173,8 @@ fn spawn_protection(
         Entity,
         &CollidingEntities,
         &Faction,
+        &UnitKind,
+        &Stats,
         &mut SpawnProtection,
         &mut Behaviour,
     )>,
@@ -118,101 +182,251 @@ fn spawn_protection(
     time: Res<Time>,
     ally_castle: Res<AllyCastle>,
     enemy_castle: Res<EnemyCastle>,
-    soldier_attack_speeds: Query<&AttackCooldownUpgrade, With<Castle>>,
     audio_assets: Res<AudioAssets>,
+    unit_registry: UnitRegistry,
+    rally_point: Res<RallyPoint>,
 ) {
-    for (entity, colliding_entities, faction, mut spawn_protection, mut behaviour) in &mut query {
+    for (entity, colliding_entities, faction, kind, stats, mut spawn_protection, mut behaviour) in
+        &mut query
+    {
         if spawn_protection.0.tick(time.delta()).finished() && colliding_entities.is_empty() {
-            *behaviour = match faction {
-                Faction::Ally => Behaviour::MoveAndAttack(enemy_castle.0.unwrap()),
-                Faction::Enemy => Behaviour::MoveAndAttack(ally_castle.0.unwrap()),
+            *behaviour = match (faction, rally_point.0) {
+                (Faction::Ally, Some(point)) => Behaviour::HoldPoint(point),
+                (Faction::Ally, None) => Behaviour::MoveAndAttack(enemy_castle.0.unwrap()),
+                (Faction::Enemy, _) => Behaviour::MoveAndAttack(ally_castle.0.unwrap()),
             };
 
-            let mut attack_speed = 1.0;
-            match faction {
-                Faction::Ally => {
-                    if let Some(entity) = ally_castle.0 {
-                        if let Ok(soldier_attack_speed) = soldier_attack_speeds.get(entity) {
-                            attack_speed *= 1.0 - soldier_attack_speed.get();
-                        }
-                    }
-                }
-                Faction::Enemy => {
-                    if let Some(entity) = enemy_castle.0 {
-                        if let Ok(soldier_attack_speed) = soldier_attack_speeds.get(entity) {
-                            attack_speed *= 1.0 - soldier_attack_speed.get();
-                        }
-                    }
-                }
-            };
+            let definition = unit_registry.get(kind);
 
             /* TODO: A bit janky if units with spawn protection overlap */
             /* Now we can remove Sensor and SpawnProtection */
             /* And add hurt and hitboxes and enemyfinder */
-            commands.entity(entity).remove::<Sensor>().remove::<SpawnProtection>()
-            .insert(DefaultBehaviour(behaviour.clone()))
-                    .with_children(|children| {
-                        children.spawn(HurtBoxBundle {
-                            collider: Collider::ball(9.0),
-                            collisionlayers: CollisionLayers::new(
-                                [faction.hurt_layer()],
-                                [faction.opposite().hit_layer()],
-                            ),
-                            ..Default::default()
-                        });
-                        children
-                            .spawn(HitBoxBundle {
-                                hitbox: HitBox {
-                                    damage: 10.0,
-                                    kind: HitBoxKind::Once(vec![]),
-                                },
-                                collider: Collider::ball(12.0), /* TODO: Get these numbers from somewhere */
-                                collisionlayers: CollisionLayers::new(
-                                    [faction.hit_layer()],
-                                    [faction.opposite().hurt_layer()],
-                                ),
-                                ..Default::default()
-                            })
-                            .insert(AttackCooldown {
-                                timer: Timer::from_seconds(attack_speed, TimerMode::Repeating),
-                            })
-                            .insert(AudioBundle {
-                                source: audio_assets.hit.clone(),
-                                ..Default::default()
-                            });
-                        children.spawn(EnemyFinderBundle {
-                            collider: Collider::ball(60.0),
-                            collisionlayers: CollisionLayers::new(
-                                [faction.hit_layer()],
-                                [faction.opposite().hurt_layer()],
-                            ),
-                            ..Default::default()
-                        });
-                    });
+            let mut entity_commands = commands.entity(entity);
+            entity_commands
+                .remove::<Sensor>()
+                .remove::<SpawnProtection>()
+                .insert(DefaultBehaviour(behaviour.clone()));
+            insert_combat(
+                &mut entity_commands,
+                *faction,
+                definition,
+                stats,
+                AttackCooldown::new(stats.get(Stat::AttackCooldown)),
+                &audio_assets.hit,
+            );
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum UnitKind {
-    Soldier,
+/// Adds the hurt box, the enemy finder and the attack described by the [`UnitDefinition`] to a unit
+/// Damage and detection radius are taken from its [`Stats`]
+pub fn insert_combat(
+    entity_commands: &mut EntityCommands,
+    faction: Faction,
+    definition: &UnitDefinition,
+    stats: &Stats,
+    attack_cooldown: AttackCooldown,
+    hit_sound: &Handle<AudioSource>,
+) {
+    if let AttackDefinition::Ranged { projectile, .. } = &definition.attack {
+        entity_commands.insert((
+            RangedAttack {
+                projectile: projectile.clone(),
+                target_in_range: false,
+            },
+            attack_cooldown.clone(),
+        ));
+    }
+
+    let source = HealthDeltaSource {
+        entity: entity_commands.id(),
+        faction,
+    };
+    entity_commands.with_children(|children| {
+        children.spawn(HurtBoxBundle {
+            collider: Collider::ball(definition.hurtbox),
+            collisionlayers: CollisionLayers::new(
+                [faction.hurt_layer()],
+                [faction.opposite().hit_layer()],
+            ),
+            ..Default::default()
+        });
+        if let AttackDefinition::Melee {
+            radius,
+            damage_type,
+            status_effect,
+            ..
+        } = definition.attack
+        {
+            children
+                .spawn(HitBoxBundle {
+                    hitbox: HitBox {
+                        damage: Damage::from_stats(stats, damage_type),
+                        kind: HitBoxKind::Once(vec![]),
+                        source: Some(source),
+                        status_effect,
+                    },
+                    collider: Collider::ball(radius),
+                    collisionlayers: CollisionLayers::new(
+                        [faction.hit_layer()],
+                        [faction.opposite().hurt_layer()],
+                    ),
+                    ..Default::default()
+                })
+                .insert(attack_cooldown)
+                .insert(AudioBundle {
+                    source: hit_sound.clone(),
+                    ..Default::default()
+                });
+        }
+        children.spawn(EnemyFinderBundle {
+            collider: Collider::ball(stats.get(Stat::DetectionRadius)),
+            collisionlayers: CollisionLayers::new(
+                [faction.hit_layer()],
+                [faction.opposite().hurt_layer()],
+            ),
+            ..Default::default()
+        });
+    });
 }
 
-impl UnitKind {
-    pub fn cost(&self) -> usize {
-        match self {
-            UnitKind::Soldier => 1,
+/// Pushes changed [`Stats`] into the components that use them
+fn apply_stats(
+    mut commands: Commands,
+    mut units: Query<(Entity, &Stats, &mut Health, Option<&Children>), Changed<Stats>>,
+    mut attack_cooldowns: Query<&mut AttackCooldown>,
+    mut hitboxes: Query<&mut HitBox>,
+    enemy_finders: Query<&Collider, With<EnemyFinder>>,
+) {
+    for (entity, stats, mut health, children) in &mut units {
+        /* Keep the share of health, a unit at full health stays at full health */
+        let max_health = stats.get(Stat::MaxHealth);
+        if health.max != max_health {
+            health.current *= max_health / health.max;
+            health.max = max_health;
+        }
+
+        /* Ranged units carry their cooldown, melee units on their hitbox */
+        let family = std::iter::once(entity).chain(
+            children
+                .into_iter()
+                .flat_map(|children| children.iter().copied()),
+        );
+        for member in family {
+            if let Ok(mut attack_cooldown) = attack_cooldowns.get_mut(member) {
+                /* Rescale, so the progress towards the next attack is kept */
+                let duration = Duration::from_secs_f32(stats.get(Stat::AttackCooldown));
+                if attack_cooldown.timer.duration() != duration {
+                    let percent = attack_cooldown.timer.percent();
+                    attack_cooldown.timer.set_duration(duration);
+                    attack_cooldown.timer.set_elapsed(duration.mul_f32(percent));
+                }
+            }
+            if let Ok(mut hitbox) = hitboxes.get_mut(member) {
+                hitbox.damage.amount = stats.get(Stat::Damage);
+                hitbox.damage.crit_chance = stats.get(Stat::CritChance);
+            }
+            if let Ok(collider) = enemy_finders.get(member) {
+                let radius = stats.get(Stat::DetectionRadius);
+                if collider.shape().as_ball().map(|ball| ball.radius) != Some(radius) {
+                    commands.entity(member).insert(Collider::ball(radius));
+                }
+            }
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Serialize, Deserialize)]
+pub enum UnitKind {
+    Soldier,
+    Archer,
+    Knight,
+}
+
 impl UnitKind {
-    pub fn texture(&self, faction: &Faction, texture_assets: &TextureAssets) -> Handle<Image> {
-        match self {
-            UnitKind::Soldier => match faction {
-                Faction::Ally => texture_assets.ally_soldier.clone(),
-                Faction::Enemy => texture_assets.enemy_soldier.clone(),
-            },
+    pub const ALL: [Self; 3] = [Self::Soldier, Self::Archer, Self::Knight];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::stats::{ModifierSource, ModifierValue, StatModifier};
+
+    use super::*;
+
+    fn run_apply_stats(world: &mut World) {
+        let mut system = IntoSystem::into_system(apply_stats);
+        system.initialize(world);
+        system.run((), world);
+        system.apply_deferred(world);
+    }
+
+    #[test]
+    fn changed_stats_rescale_health_and_attack_cooldown() {
+        let mut world = World::new();
+        let mut attack_cooldown = AttackCooldown::new(1.0);
+        attack_cooldown
+            .timer
+            .set_elapsed(Duration::from_secs_f32(0.5));
+        let unit = world
+            .spawn((
+                Stats::new([(Stat::MaxHealth, 100.0), (Stat::AttackCooldown, 1.0)]),
+                Health {
+                    current: 50.0,
+                    max: 100.0,
+                },
+                attack_cooldown,
+            ))
+            .id();
+        run_apply_stats(&mut world);
+
+        {
+            let mut stats = world.get_mut::<Stats>(unit).unwrap();
+            stats.modifiers.set(StatModifier {
+                source: ModifierSource::ShieldUpgrade,
+                stat: Stat::MaxHealth,
+                value: ModifierValue::Multiply(2.0),
+            });
+            stats.modifiers.set(StatModifier {
+                source: ModifierSource::AttackCooldownUpgrade,
+                stat: Stat::AttackCooldown,
+                value: ModifierValue::Multiply(0.5),
+            });
         }
+        run_apply_stats(&mut world);
+
+        let health = world.get::<Health>(unit).unwrap();
+        assert_eq!((health.current, health.max), (100.0, 200.0));
+        let timer = &world.get::<AttackCooldown>(unit).unwrap().timer;
+        assert_eq!(timer.duration(), Duration::from_secs_f32(0.5));
+        assert_eq!(timer.elapsed(), Duration::from_secs_f32(0.25));
+    }
+
+    #[test]
+    fn unchanged_max_health_keeps_the_health() {
+        let mut world = World::new();
+        let unit = world
+            .spawn((
+                Stats::new([(Stat::MaxHealth, 100.0), (Stat::Armor, 0.0)]),
+                Health {
+                    current: 30.0,
+                    max: 100.0,
+                },
+            ))
+            .id();
+        run_apply_stats(&mut world);
+
+        world
+            .get_mut::<Stats>(unit)
+            .unwrap()
+            .modifiers
+            .set(StatModifier {
+                source: ModifierSource::ShieldUpgrade,
+                stat: Stat::Armor,
+                value: ModifierValue::Add(10.0),
+            });
+        run_apply_stats(&mut world);
+
+        let health = world.get::<Health>(unit).unwrap();
+        assert_eq!((health.current, health.max), (30.0, 100.0));
     }
 }
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::components::Collider;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    common::{attributes::Health, Faction},
+    GameState,
+};
+
+use super::{
+    behaviour::{Behaviour, DefaultBehaviour},
+    UnitKind,
+};
+
+pub struct OrderPlugin;
+
+/// This plugin handles selecting allied units and the orders given to them
+/// Order logic is only active during the State `GameState::Playing`
+impl Plugin for OrderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            process_order_commands
+                .in_set(SimulationSet::Commands)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Allied unit that receives the next [`UnitOrder`]
+#[derive(Debug, Default, Component)]
+pub struct Selected;
+
+/// What the player can tell the selected units to do
+/// Points are world positions rounded to whole pixels to keep recordings exact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitOrder {
+    /// Attack the enemy at the point, or move there if there is none
+    Target {
+        x: i32,
+        y: i32,
+    },
+    HoldPosition,
+    ReturnToCastle,
+}
+
+fn process_order_commands(
+    mut commands: Commands,
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut units: Query<
+        (
+            Entity,
+            &Faction,
+            &GlobalTransform,
+            &mut Behaviour,
+            &mut DefaultBehaviour,
+            Option<&Selected>,
+        ),
+        With<UnitKind>,
+    >,
+    targets: Query<(Entity, &Faction, &GlobalTransform, &Collider), With<Health>>,
+) {
+    for ev in playercommand_evr.read() {
+        match ev.action {
+            PlayerAction::SelectUnits {
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+            } => {
+                let area = Rect::new(min_x as f32, min_y as f32, max_x as f32, max_y as f32);
+                for (entity, faction, transform, _, _, selected) in &units {
+                    let inside = *faction == Faction::Ally
+                        && area.contains(transform.translation().truncate());
+                    if inside && selected.is_none() {
+                        commands.entity(entity).insert(Selected);
+                    } else if !inside && selected.is_some() {
+                        commands.entity(entity).remove::<Selected>();
+                    }
+                }
+            }
+            PlayerAction::OrderUnits(order) => {
+                let order_behaviour = match order {
+                    UnitOrder::Target { x, y } => {
+                        let point = Vec2::new(x as f32, y as f32);
+                        Some(
+                            target_at(&targets, point)
+                                .map_or(Behaviour::MoveOrder(point), Behaviour::AttackOrder),
+                        )
+                    }
+                    UnitOrder::ReturnToCastle => Some(Behaviour::ReturnToCastle),
+                    UnitOrder::HoldPosition => None,
+                };
+
+                for (_, _, transform, mut behaviour, mut default_behaviour, selected) in &mut units
+                {
+                    if selected.is_none() {
+                        continue;
+                    }
+                    match &order_behaviour {
+                        Some(order_behaviour) => *behaviour = order_behaviour.clone(),
+                        None => {
+                            let point = transform.translation().truncate();
+                            *behaviour = Behaviour::HoldPoint(point);
+                            default_behaviour.0 = Behaviour::HoldPoint(point);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Closest enemy whose collider contains the point
+fn target_at(
+    targets: &Query<(Entity, &Faction, &GlobalTransform, &Collider), With<Health>>,
+    point: Vec2,
+) -> Option<Entity> {
+    targets
+        .iter()
+        .filter(|(_, faction, ..)| **faction == Faction::Enemy)
+        .filter_map(|(entity, _, transform, collider)| {
+            let radius = collider.shape().as_ball()?.radius;
+            let distance = transform.translation().truncate().distance(point);
+            (distance <= radius).then_some((entity, distance))
+        })
+        .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+        .map(|(entity, _)| entity)
+}
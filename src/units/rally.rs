@@ -0,0 +1,185 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, ButtonState},
+    prelude::*,
+};
+
+use crate::{
+    castle::EnemyCastle,
+    command::{PendingPlayerActions, PlayerAction, PlayerCommand, SimulationSet},
+    common::{viewport_to_world_position, Faction},
+    session::{MatchEntity, MatchSetupSet},
+    GameState,
+};
+
+use super::{
+    behaviour::{Behaviour, DefaultBehaviour},
+    order::Selected,
+    UnitKind,
+};
+
+pub struct RallyPlugin;
+
+/// This plugin handles the rally point allied units gather at instead of charging the enemy castle
+/// Without a selection right click sets it, shift + right click also calls back the units on the battlefield and R releases them
+/// Rally logic is only active during the State `GameState::Playing`
+impl Plugin for RallyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RallyPoint>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_rally_point.in_set(MatchSetupSet),
+            )
+            .add_systems(
+                Update,
+                (emit_rally_actions, update_rally_flag).run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                process_rally_commands
+                    .in_set(SimulationSet::Commands)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Where newly spawned allied units gather, `None` if they attack right away
+#[derive(Debug, Default, Resource)]
+pub struct RallyPoint(pub Option<Vec2>);
+
+/// Marks the flag drawn at the rally point
+#[derive(Debug, Component)]
+struct RallyFlag;
+
+fn reset_rally_point(mut rally_point: ResMut<RallyPoint>) {
+    rally_point.0 = None;
+}
+
+fn emit_rally_actions(
+    mut mousebtn_evr: EventReader<MouseButtonInput>,
+    mut keyboard_evr: EventReader<KeyboardInput>,
+    keys: Res<Input<KeyCode>>,
+    mut player_actions: ResMut<PendingPlayerActions>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    selected: Query<(), With<Selected>>,
+) {
+    for ev in mousebtn_evr.read() {
+        /* Right click orders the selected units instead */
+        if ev.button != MouseButton::Right
+            || ev.state != ButtonState::Pressed
+            || !selected.is_empty()
+        {
+            continue;
+        }
+        let (Ok(window), Ok((camera, camera_transform))) =
+            (windows.get(ev.window), cameras.get_single())
+        else {
+            continue;
+        };
+
+        if let Some(world_position) = viewport_to_world_position(window, camera, camera_transform) {
+            player_actions.send(PlayerAction::SetRallyPoint {
+                x: world_position.x.round() as i32,
+                y: world_position.y.round() as i32,
+                recall: keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            });
+        }
+    }
+
+    for ev in keyboard_evr.read() {
+        if let Some(KeyCode::R) = ev.key_code {
+            if ev.state == ButtonState::Pressed {
+                player_actions.send(PlayerAction::ReleaseRally);
+            }
+        }
+    }
+}
+
+fn process_rally_commands(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut rally_point: ResMut<RallyPoint>,
+    mut units: Query<(&Faction, &mut Behaviour, &mut DefaultBehaviour), With<UnitKind>>,
+    enemy_castle: Res<EnemyCastle>,
+) {
+    for ev in playercommand_evr.read() {
+        match ev.action {
+            PlayerAction::SetRallyPoint { x, y, recall } => {
+                let point = Vec2::new(x as f32, y as f32);
+                rally_point.0 = Some(point);
+
+                /* Units already holding the previous rally point move along */
+                for (faction, mut behaviour, mut default_behaviour) in &mut units {
+                    let holding = matches!(default_behaviour.0, Behaviour::HoldPoint(_));
+                    if *faction == Faction::Ally && (recall || holding) {
+                        *behaviour = Behaviour::HoldPoint(point);
+                        default_behaviour.0 = Behaviour::HoldPoint(point);
+                    }
+                }
+            }
+            PlayerAction::ReleaseRally => {
+                rally_point.0 = None;
+
+                let Some(target) = enemy_castle.0 else {
+                    continue;
+                };
+                for (faction, mut behaviour, mut default_behaviour) in &mut units {
+                    if *faction == Faction::Ally
+                        && matches!(default_behaviour.0, Behaviour::HoldPoint(_))
+                    {
+                        default_behaviour.0 = Behaviour::MoveAndAttack(target);
+                        /* Units in a fight finish it first */
+                        if let Behaviour::HoldPoint(_) = *behaviour {
+                            *behaviour = Behaviour::MoveAndAttack(target);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_rally_flag(
+    mut commands: Commands,
+    rally_point: Res<RallyPoint>,
+    flags: Query<Entity, With<RallyFlag>>,
+) {
+    if !rally_point.is_changed() {
+        return;
+    }
+
+    for entity in &flags {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if let Some(point) = rally_point.0 {
+        commands
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_translation(point.extend(0.5))),
+                RallyFlag,
+                MatchEntity,
+            ))
+            .with_children(|children| {
+                /* Pole */
+                children.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.3, 0.2, 0.1),
+                        custom_size: Some(Vec2::new(4.0, 40.0)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(0.0, 20.0, 0.0),
+                    ..Default::default()
+                });
+                /* Cloth */
+                children.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.2, 0.4, 0.9),
+                        custom_size: Some(Vec2::new(24.0, 16.0)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(14.0, 32.0, 0.0),
+                    ..Default::default()
+                });
+            });
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::components::{
+    Collider, ColliderParent, CollidingEntities, CollisionLayers, LinearVelocity, RigidBody,
+};
+
+use crate::{
+    command::SimulationSet,
+    common::{
+        attributes::HealthDeltaSource, damage::Damage, stats::Stats, status::StatusEffects, Faction,
+    },
+    physics::hit_detection::{hit_detection, HitBox, HitBoxBundle, HitBoxKind},
+    session::MatchEntity,
+    GameState,
+};
+
+use super::{
+    behaviour::{Behaviour, EnemyFinder},
+    definition::ProjectileDefinition,
+    AttackCooldown,
+};
+
+pub struct RangedPlugin;
+
+/// This plugin handles units that attack from a distance by firing projectiles
+/// Ranged logic is only active during the State `GameState::Playing`
+impl Plugin for RangedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (ranged_attack, despawn_projectile.after(hit_detection))
+                .in_set(SimulationSet::Combat)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct RangedAttack {
+    pub projectile: ProjectileDefinition,
+    /// Whether the EnemyFinder currently touches the target, in which case the unit stops and fires
+    pub target_in_range: bool,
+}
+
+#[derive(Debug, Component)]
+pub struct Projectile {
+    pub lifetime: Timer,
+}
+
+fn ranged_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rangers: Query<(
+        &mut RangedAttack,
+        &mut AttackCooldown,
+        &Behaviour,
+        &GlobalTransform,
+        &Faction,
+        &Stats,
+        Option<&StatusEffects>,
+    )>,
+    enemy_finders: Query<(&ColliderParent, &CollidingEntities), With<EnemyFinder>>,
+    collider_parents: Query<&ColliderParent>,
+    transforms: Query<&GlobalTransform>,
+) {
+    for (parent, colliding_entities) in &enemy_finders {
+        if let Ok((
+            mut ranged_attack,
+            mut cooldown,
+            behaviour,
+            src_transform,
+            faction,
+            stats,
+            status_effects,
+        )) = rangers.get_mut(parent.get())
+        {
+            let (Behaviour::MoveAndAttack(target) | Behaviour::AttackOrder(target)) = *behaviour
+            else {
+                ranged_attack.target_in_range = false;
+                continue;
+            };
+
+            ranged_attack.target_in_range = colliding_entities.iter().any(|entity| {
+                collider_parents
+                    .get(*entity)
+                    .is_ok_and(|collider_parent| collider_parent.get() == target)
+            });
+
+            /* Stunned units hold their cooldown */
+            let stunned = status_effects.is_some_and(|status_effects| status_effects.is_stunned());
+            if !ranged_attack.target_in_range
+                || stunned
+                || !cooldown.timer.tick(time.delta()).just_finished()
+            {
+                continue;
+            }
+
+            if let Ok(dst_transform) = transforms.get(target) {
+                let src_point = src_transform.translation().truncate();
+                let dst_point = dst_transform.translation().truncate();
+                let direction = (dst_point - src_point).normalize_or_zero();
+
+                spawn_projectile(
+                    &mut commands,
+                    HealthDeltaSource {
+                        entity: parent.get(),
+                        faction: *faction,
+                    },
+                    &ranged_attack.projectile,
+                    Damage::from_stats(stats, ranged_attack.projectile.damage_type),
+                    src_transform.translation(),
+                    direction,
+                );
+            }
+        }
+    }
+}
+
+fn spawn_projectile(
+    commands: &mut Commands,
+    source: HealthDeltaSource,
+    projectile: &ProjectileDefinition,
+    damage: Damage,
+    translation: Vec3,
+    direction: Vec2,
+) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(projectile.size)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(translation),
+            ..Default::default()
+        },
+        HitBoxBundle {
+            hitbox: HitBox {
+                damage,
+                kind: HitBoxKind::Once(vec![]),
+                source: Some(source),
+                status_effect: projectile.status_effect,
+            },
+            collider: Collider::ball(projectile.radius),
+            collisionlayers: CollisionLayers::new(
+                [source.faction.hit_layer()],
+                [source.faction.opposite().hurt_layer()],
+            ),
+            ..Default::default()
+        },
+        RigidBody::Kinematic,
+        LinearVelocity(direction * projectile.speed),
+        Projectile {
+            lifetime: Timer::from_seconds(projectile.lifetime, TimerMode::Once),
+        },
+        MatchEntity,
+    ));
+}
+
+fn despawn_projectile(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectiles: Query<(Entity, &mut Projectile, &HitBox)>,
+) {
+    for (entity, mut projectile, hitbox) in &mut projectiles {
+        if projectile.lifetime.tick(time.delta()).finished() || hitbox.has_hit() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,16 +1,72 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    castle::{
+        commander::commander_think,
+        economy::{GoldCategory, RejectionReason, Treasury},
+        tech::{process_research_commands, ResearchCompleted, TechEffect, TechRegistry},
+        AllyCastle, Castle,
+    },
+    command::{PlayerAction, PlayerCommand, SimulationSet},
+    common::{
+        stats::{ModifierSource, ModifierValue, Stat, StatModifier, StatModifiers, Stats},
+        Faction,
+    },
+    GameState,
+};
+
+use super::UnitKind;
 
 pub struct UpgradePlugin;
 
 /// This plugin handles attributes related stuff like health
 /// Attribure logic is only active during the State `GameState::Playing`
 impl Plugin for UpgradePlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                process_unit_upgrade_commands,
+                update_unit_modifiers::<ShieldUpgrade>,
+                update_unit_modifiers::<AttackCooldownUpgrade>,
+                /* Research is completed by the player and the enemy commander */
+                apply_tech_modifiers
+                    .after(process_research_commands)
+                    .after(commander_think),
+            )
+                .chain()
+                .in_set(SimulationSet::Commands)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Modifiers a castle passes on to every unit it spawns
+#[derive(Debug, Default, Component)]
+pub struct UnitModifiers(pub StatModifiers);
+
+/// Which units of a faction a [`UnitUpgrade`] applies to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpgradeScope {
+    /// Only units spawned after the purchase
+    FutureUnits,
+    /// Units already on the battlefield as well
+    #[default]
+    AllUnits,
+}
+
+/// Castle upgrade that modifies the [`Stats`] of the units of its castle
+pub trait UnitUpgrade: Component {
+    fn scope(&self) -> UpgradeScope;
+
+    fn modifier(&self) -> StatModifier;
 }
 
 #[derive(Debug, Default, Component)]
 pub struct ShieldUpgrade {
     level: usize,
+    scope: UpgradeScope,
 }
 
 impl ShieldUpgrade {
@@ -20,6 +76,10 @@ impl ShieldUpgrade {
         self.level
     }
 
+    pub fn is_max_level(&self) -> bool {
+        self.level >= Self::MAX_LEVEL
+    }
+
     pub fn level_up(&mut self) -> bool {
         if self.level < Self::MAX_LEVEL {
             self.level += 1;
@@ -33,18 +93,27 @@ impl ShieldUpgrade {
         (self.level + 1) * 3
     }
 
+    /// Armor, so the last level halves the physical damage units take
     pub fn get(&self) -> f32 {
-        (self.level as f32) * 12.5
+        (self.level as f32) * 10.0
     }
 
     pub fn new(level: usize) -> Self {
-        Self { level }
+        Self {
+            level,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_scope(self, scope: UpgradeScope) -> Self {
+        Self { scope, ..self }
     }
 }
 
 #[derive(Debug, Default, Component)]
 pub struct AttackCooldownUpgrade {
     level: usize,
+    scope: UpgradeScope,
 }
 
 impl AttackCooldownUpgrade {
@@ -55,6 +124,10 @@ impl AttackCooldownUpgrade {
         self.level
     }
 
+    pub fn is_max_level(&self) -> bool {
+        self.level >= Self::MAX_LEVEL
+    }
+
     pub fn level_up(&mut self) -> bool {
         if self.level < Self::MAX_LEVEL {
             self.level += 1;
@@ -73,6 +146,140 @@ impl AttackCooldownUpgrade {
     }
 
     pub fn new(level: usize) -> Self {
-        Self { level }
+        Self {
+            level,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_scope(self, scope: UpgradeScope) -> Self {
+        Self { scope, ..self }
+    }
+}
+
+impl UnitUpgrade for ShieldUpgrade {
+    fn scope(&self) -> UpgradeScope {
+        self.scope
+    }
+
+    fn modifier(&self) -> StatModifier {
+        StatModifier {
+            source: ModifierSource::ShieldUpgrade,
+            stat: Stat::Armor,
+            value: ModifierValue::Add(self.get()),
+        }
+    }
+}
+
+impl UnitUpgrade for AttackCooldownUpgrade {
+    fn scope(&self) -> UpgradeScope {
+        self.scope
+    }
+
+    fn modifier(&self) -> StatModifier {
+        StatModifier {
+            source: ModifierSource::AttackCooldownUpgrade,
+            stat: Stat::AttackCooldown,
+            value: ModifierValue::Multiply(1.0 - self.get()),
+        }
+    }
+}
+
+fn process_unit_upgrade_commands(
+    mut playercommand_evr: EventReader<PlayerCommand>,
+    mut upgrades: Query<(&mut ShieldUpgrade, &mut AttackCooldownUpgrade), With<Castle>>,
+    ally_castle: Res<AllyCastle>,
+    mut treasury: Treasury,
+) {
+    for ev in playercommand_evr.read() {
+        let Some(entity) = ally_castle.0 else {
+            continue;
+        };
+        let Ok((mut soldier_shield, mut soldier_attackspeed)) = upgrades.get_mut(entity) else {
+            continue;
+        };
+
+        match ev.action {
+            PlayerAction::UpgradeShield => {
+                if soldier_shield.is_max_level() {
+                    treasury.reject(GoldCategory::Upgrades, RejectionReason::MaxLevel);
+                } else if treasury.try_spend(soldier_shield.cost(), GoldCategory::Upgrades) {
+                    soldier_shield.level_up();
+                }
+            }
+            PlayerAction::UpgradeAttackCooldown => {
+                if soldier_attackspeed.is_max_level() {
+                    treasury.reject(GoldCategory::Upgrades, RejectionReason::MaxLevel);
+                } else if treasury.try_spend(soldier_attackspeed.cost(), GoldCategory::Upgrades) {
+                    soldier_attackspeed.level_up();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Passes the modifier on to the units the castle spawns from now on and, depending on the scope, to the ones already spawned
+fn push_unit_modifier(
+    modifier: StatModifier,
+    scope: UpgradeScope,
+    castle_faction: &Faction,
+    unit_modifiers: &mut UnitModifiers,
+    units: &mut Query<(&Faction, &mut Stats), With<UnitKind>>,
+) {
+    unit_modifiers.0.set(modifier);
+
+    if scope == UpgradeScope::AllUnits {
+        for (faction, mut stats) in units {
+            if faction == castle_faction
+                && stats.modifiers.get(modifier.source, modifier.stat) != Some(&modifier)
+            {
+                stats.modifiers.set(modifier);
+            }
+        }
+    }
+}
+
+fn update_unit_modifiers<T: UnitUpgrade>(
+    mut castles: Query<(&T, &Faction, &mut UnitModifiers), (With<Castle>, Changed<T>)>,
+    mut units: Query<(&Faction, &mut Stats), With<UnitKind>>,
+) {
+    for (upgrade, faction, mut unit_modifiers) in &mut castles {
+        push_unit_modifier(
+            upgrade.modifier(),
+            upgrade.scope(),
+            faction,
+            &mut unit_modifiers,
+            &mut units,
+        );
+    }
+}
+
+fn apply_tech_modifiers(
+    mut researchcompleted_evr: EventReader<ResearchCompleted>,
+    mut castles: Query<&mut UnitModifiers, With<Castle>>,
+    mut units: Query<(&Faction, &mut Stats), With<UnitKind>>,
+    tech_registry: TechRegistry,
+) {
+    for ev in researchcompleted_evr.read() {
+        let Ok(mut unit_modifiers) = castles.get_mut(ev.castle) else {
+            continue;
+        };
+
+        for effect in &tech_registry.get().nodes()[ev.node].effects {
+            if let TechEffect::UnitModifier(stat, value) = *effect {
+                push_unit_modifier(
+                    StatModifier {
+                        source: ModifierSource::Tech(ev.node),
+                        stat,
+                        value,
+                    },
+                    UpgradeScope::AllUnits,
+                    &ev.faction,
+                    &mut unit_modifiers,
+                    &mut units,
+                );
+            }
+        }
     }
 }
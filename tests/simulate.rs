@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use bevy_jam4_click_defense::simulation::{
+    run_simulation, Outcome, PlayerPolicy, SimulationConfig,
+};
+
+#[test]
+fn idle_player_spends_no_gold() {
+    let summary = run_simulation(SimulationConfig {
+        policy: PlayerPolicy::Idle,
+        max_duration: Duration::from_secs(30),
+        ..Default::default()
+    });
+
+    assert_eq!(summary.outcome, Outcome::Timeout);
+    assert_eq!(summary.stats.gold_spent, 0);
+    assert_eq!(summary.stats.units_spawned.ally, 0);
+}
+
+#[test]
+fn same_seed_produces_same_match() {
+    let config = SimulationConfig {
+        policy: PlayerPolicy::Soldiers,
+        seed: 42,
+        max_duration: Duration::from_secs(30),
+        ..Default::default()
+    };
+
+    let first = run_simulation(config.clone());
+    let second = run_simulation(config);
+
+    assert_eq!(first.to_string(), second.to_string());
+}
+
+#[test]
+fn replay_reproduces_match() {
+    let played = run_simulation(SimulationConfig {
+        policy: PlayerPolicy::ShieldThenSoldiers,
+        seed: 7,
+        max_duration: Duration::from_secs(30),
+        ..Default::default()
+    });
+    assert!(!played.recording.commands.is_empty());
+
+    let replayed = run_simulation(SimulationConfig {
+        replay: Some(played.recording.clone()),
+        max_duration: Duration::from_secs(30),
+        ..Default::default()
+    });
+
+    assert_eq!(played.to_string(), replayed.to_string());
+    assert_eq!(played.recording, replayed.recording);
+}